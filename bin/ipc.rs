@@ -1,6 +1,6 @@
 use std::error::Error;
 use std::ffi::OsStr;
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
 use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
 
@@ -9,7 +9,15 @@ use clap::{ColorChoice, Parser, Subcommand, ValueEnum};
 use istat::bail;
 use istat::i3::{I3Button, I3ClickEvent, I3Modifier};
 use istat::ipc::get_socket_path;
-use istat::ipc::protocol::{encode_ipc_msg, IpcBarEvent, IpcMessage, IpcReply, IPC_HEADER_LEN};
+use istat::ipc::protocol::{
+    encode_ipc_msg,
+    IpcBarEvent,
+    IpcEvent,
+    IpcMessage,
+    IpcReply,
+    IpcResult,
+    IPC_HEADER_LEN,
+};
 use serde_json::Value;
 
 #[derive(Debug, Parser)]
@@ -29,8 +37,13 @@ enum CliCommand {
     /// Sends a signal to all events to trigger a refresh. Note that some items completely ignore all
     /// events, and thus won't receive this refresh events.
     RefreshAll,
+    /// Reloads the configuration file. Items whose configuration hasn't changed are left running.
+    Reload,
     /// Returns the current bar as JSON.
     GetBar,
+    /// Prints events as they happen (one JSON object per line), until the bar exits. This includes
+    /// bar updates, click events, item errors, item restarts and theme changes.
+    Watch,
     /// Returns the current configuration.
     GetConfig {
         /// JSON Pointer for the config https://datatracker.ietf.org/doc/html/rfc6901
@@ -94,6 +107,35 @@ enum CliCommand {
         #[clap(trailing_var_arg = true)]
         args: Vec<String>,
     },
+    /// Add a new item to the bar, for example:
+    ///
+    /// `istat-ipc add-item '{"type":"raw","name":"deploy","full_text":"deploying..."}'`
+    ///
+    /// Items added this way are removed when the configuration is reloaded.
+    AddItem {
+        /// The item's configuration as JSON (the same as an item in the configuration file)
+        json: String,
+        /// Where to insert the item, if not provided the item is added to the end of the bar
+        #[clap(long, short)]
+        index: Option<usize>,
+    },
+    /// Remove an item from the bar.
+    RemoveItem {
+        /// The target bar item: can be an index or the name of the item
+        target: String,
+    },
+    /// Replace the configuration of an item in the bar, and restart it.
+    ReplaceItem {
+        /// The target bar item: can be an index or the name of the item
+        target: String,
+        /// The item's new configuration as JSON (the same as an item in the configuration file)
+        json: String,
+    },
+    /// Returns the recent log lines of a bar item as a JSON array (e.g., a script's STDERR).
+    GetItemLogs {
+        /// The target bar item: can be an index or the name of the item
+        target: String,
+    },
     Shutdown,
 }
 
@@ -173,6 +215,45 @@ fn send_message(
     Ok(serde_json::from_slice(&buf[IPC_HEADER_LEN..n])?)
 }
 
+fn watch_events(socket_path: impl AsRef<OsStr>) -> Result<(), Box<dyn Error>> {
+    let mut stream = UnixStream::connect(socket_path.as_ref())?;
+
+    let msg = encode_ipc_msg(IpcMessage::Subscribe)?;
+    if let Err(e) = stream.write_all(&msg) {
+        bail!("Error writing to socket: {}", e);
+    }
+
+    // the first message is the reply to our subscription, after that everything is an event
+    let mut subscribed = false;
+    let mut header = [0; IPC_HEADER_LEN];
+    loop {
+        match stream.read_exact(&mut header) {
+            Ok(()) => {}
+            // the connection was closed, so the bar has exited
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => bail!("Error reading from socket: {}", e),
+        }
+
+        let mut body = vec![0; u64::from_le_bytes(header) as usize];
+        if let Err(e) = stream.read_exact(&mut body) {
+            bail!("Error reading from socket: {}", e);
+        }
+
+        if !subscribed {
+            match serde_json::from_slice::<IpcReply>(&body)? {
+                IpcReply::Result(IpcResult::Success(_)) => subscribed = true,
+                reply => bail!("failed to subscribe: {}", serde_json::to_string(&reply)?),
+            }
+            continue;
+        }
+
+        let event = serde_json::from_slice::<IpcEvent>(&body)?;
+        println!("{}", serde_json::to_string(&event)?);
+    }
+
+    Ok(())
+}
+
 fn send_and_print_response(
     socket_path: impl AsRef<OsStr>,
     msg: IpcMessage,
@@ -209,7 +290,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         CliCommand::Shutdown => send_and_print_response(&socket_path, IpcMessage::Shutdown)?,
         CliCommand::Info => send_and_print_response(&socket_path, IpcMessage::Info)?,
         CliCommand::GetBar => send_and_print_response(&socket_path, IpcMessage::GetBar)?,
+        CliCommand::Watch => watch_events(&socket_path)?,
         CliCommand::RefreshAll => send_and_print_response(&socket_path, IpcMessage::RefreshAll)?,
+        CliCommand::Reload => send_and_print_response(&socket_path, IpcMessage::Reload)?,
         CliCommand::GetConfig { pointer: None } => {
             send_and_print_response(&socket_path, IpcMessage::GetConfig)?
         }
@@ -312,6 +395,26 @@ fn main() -> Result<(), Box<dyn Error>> {
                 event: IpcBarEvent::Custom(args),
             },
         )?,
+        CliCommand::AddItem { json, index } => send_and_print_response(
+            &socket_path,
+            IpcMessage::AddItem {
+                index,
+                item: serde_json::from_str(&json)?,
+            },
+        )?,
+        CliCommand::RemoveItem { target } => {
+            send_and_print_response(&socket_path, IpcMessage::RemoveItem { target })?
+        }
+        CliCommand::GetItemLogs { target } => {
+            send_and_print_response(&socket_path, IpcMessage::GetItemLogs { target })?
+        }
+        CliCommand::ReplaceItem { target, json } => send_and_print_response(
+            &socket_path,
+            IpcMessage::ReplaceItem {
+                target,
+                item: serde_json::from_str(&json)?,
+            },
+        )?,
     }
 
     Ok(())
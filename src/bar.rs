@@ -0,0 +1,690 @@
+use std::error::Error;
+use std::rc::Rc;
+use std::time::Duration;
+
+use hex_color::HexColor;
+use serde_json::Value;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, sleep_until, Instant};
+
+use crate::cli::Cli;
+use crate::config::{AppConfig, Item};
+use crate::context::{Context, ItemUpdater, SharedState, StopAction};
+use crate::dispatcher::Dispatcher;
+use crate::i3::{I3Item, I3Markup};
+use crate::ipc::protocol::IpcEvent;
+use crate::output::Output;
+use crate::util::RcCell;
+
+/// Manages the items in the bar: spawns a task for each item, and keeps track of those tasks so
+/// they can be replaced at runtime (e.g., when the configuration is reloaded).
+///
+/// Each task is given a unique id which never changes, even if its item moves to a different index
+/// in the bar. This means updates sent from items are always routed to the right place.
+#[derive(Debug)]
+pub struct Bar {
+    args: Cli,
+    config: RcCell<AppConfig>,
+    state: RcCell<SharedState>,
+    /// A list of items which represents the i3 bar
+    items: RcCell<Vec<I3Item>>,
+    /// Maps each index in the bar to the id of the task running that item
+    ids: RcCell<Vec<usize>>,
+    /// Used to send events to each bar item
+    dispatcher: RcCell<Dispatcher>,
+    /// Used by items to send updates back to the bar
+    tx: Sender<(I3Item, usize)>,
+    /// Whether the bar is running or stopped (i3 stops the bar when it's hidden)
+    running: watch::Sender<bool>,
+    /// Used to send events to IPC subscribers
+    events: broadcast::Sender<IpcEvent>,
+    /// Formats the bar for the status bar it's printed to
+    output: Rc<dyn Output>,
+    tasks: Vec<JoinHandle<()>>,
+    next_id: usize,
+}
+
+impl Bar {
+    /// Create the bar, spawn tasks for each bar item and start printing the bar to STDOUT.
+    pub fn new(args: Cli, config: RcCell<AppConfig>) -> Result<RcCell<Bar>, Box<dyn Error>> {
+        let (bar, rx) = Bar::spawn(args, config);
+
+        // setup listener for handling item updates and printing the bar to STDOUT
+        bar.handle_item_updates(rx)?;
+
+        Ok(RcCell::new(bar))
+    }
+
+    /// Start every item, and wait until each one has updated (or finished running) before returning
+    /// the bar. Items which haven't updated before the timeout are left empty.
+    pub async fn once(
+        args: Cli,
+        config: RcCell<AppConfig>,
+        timeout: Duration,
+    ) -> Result<Vec<I3Item>, Box<dyn Error>> {
+        let (mut bar, mut rx) = Bar::spawn(args, config);
+        let deadline = Instant::now() + timeout;
+        let mut updated = vec![false; bar.tasks.len()];
+
+        loop {
+            let done = bar
+                .tasks
+                .iter()
+                .zip(&updated)
+                .all(|(task, updated)| *updated || task.is_finished());
+            if done {
+                break;
+            }
+
+            tokio::select! {
+                Some((i3_item, id)) = rx.recv() => {
+                    if let Some(idx) = index_of(&bar.ids, id) {
+                        bar.items[idx] = prepare_item(&bar.config, idx, i3_item);
+                        updated[idx] = true;
+                    }
+                }
+                // items which finish without updating are noticed here
+                _ = sleep(Duration::from_millis(50)) => {}
+                _ = sleep_until(deadline) => {
+                    log::warn!("timed out waiting for items to update");
+                    break;
+                }
+            }
+        }
+
+        for task in bar.tasks.drain(..) {
+            task.abort();
+        }
+
+        Ok(build_bar(&bar.config, &bar.items))
+    }
+
+    /// Create the bar and spawn tasks for each bar item. Items send their updates to the returned receiver.
+    fn spawn(args: Cli, config: RcCell<AppConfig>) -> (Bar, Receiver<(I3Item, usize)>) {
+        let item_count = config.items.len();
+        let (tx, rx) = mpsc::channel(item_count + 1);
+        let (events, _) = broadcast::channel(32);
+        let dispatcher = Dispatcher::new(item_count, config.clone(), events.clone());
+        let output = config.output.create(config.socket());
+
+        let mut bar = Bar {
+            args,
+            config,
+            state: SharedState::new(),
+            items: RcCell::new(vec![I3Item::empty(); item_count]),
+            ids: RcCell::new(Vec::with_capacity(item_count)),
+            dispatcher: RcCell::new(dispatcher),
+            tx,
+            running: watch::channel(true).0,
+            events,
+            output,
+            tasks: Vec::with_capacity(item_count),
+            next_id: 0,
+        };
+
+        // iterate config and create bar items
+        for idx in 0..item_count {
+            let id = bar.next_id();
+            bar.ids.push(id);
+            let task = bar.spawn_item(id, &bar.config.items[idx]);
+            bar.tasks.push(task);
+        }
+
+        (bar, rx)
+    }
+
+    pub fn items(&self) -> RcCell<Vec<I3Item>> {
+        self.items.clone()
+    }
+
+    /// The log lines recorded by the item at the given index, see `Context::log`.
+    pub fn item_logs(&self, idx: usize) -> Result<Vec<String>, Box<dyn Error>> {
+        match self.ids.get(idx) {
+            Some(id) => Ok(self.state.logs(*id)),
+            None => bail!("no item found with index: {}", idx),
+        }
+    }
+
+    pub fn dispatcher(&self) -> RcCell<Dispatcher> {
+        self.dispatcher.clone()
+    }
+
+    /// Receive events about the bar, see `IpcEvent`.
+    pub fn subscribe(&self) -> broadcast::Receiver<IpcEvent> {
+        self.events.subscribe()
+    }
+
+    /// Send an event to all subscribers, if there are any.
+    pub fn emit(&self, event: IpcEvent) {
+        // NOTE: this only fails if there are no subscribers
+        let _ = self.events.send(event);
+    }
+
+    /// Called when i3 hides the bar: items stop refreshing, and the bar is no longer printed.
+    pub fn stop(&mut self) {
+        log::debug!("bar stopped");
+        self.running.send_replace(false);
+    }
+
+    /// Called when i3 shows the bar again: print the bar and refresh all items.
+    pub async fn cont(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("bar continued");
+        self.running.send_replace(true);
+        print_bar(&self.config, &self.items, &self.events, &*self.output);
+        self.dispatcher.signal_all().await
+    }
+
+    /// Read the configuration again, and update the bar to match it.
+    /// Items which haven't changed are left running, all other items are stopped or started.
+    pub async fn reload(&mut self) -> Result<(), Box<dyn Error>> {
+        let new_config = self.config.reload(&self.args).await?;
+
+        let to_values = |items: &[Item]| {
+            items
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let reused = match_items(
+            &to_values(&self.config.items)?,
+            &to_values(&new_config.items)?,
+        );
+
+        self.update(new_config, reused)?;
+        log::info!("reloaded config, bar now has {} items", self.ids.len());
+
+        Ok(())
+    }
+
+    /// Insert a new item into the bar at the given index (or at the end if there's no index).
+    /// Returns the index of the new item.
+    pub fn insert_item(&mut self, idx: Option<usize>, item: Item) -> Result<usize, Box<dyn Error>> {
+        let len = self.config.items.len();
+        let idx = idx.or(item.common.index).unwrap_or(len).min(len);
+
+        let mut items = self.config.items.clone();
+        items.insert(idx, item);
+        let new_config = self.config.with_items(items)?;
+
+        let mut reused = (0..len).map(Some).collect::<Vec<_>>();
+        reused.insert(idx, None);
+        self.update(new_config, reused)?;
+        log::info!("added item[{}]", idx);
+
+        Ok(idx)
+    }
+
+    /// Remove the item at the given index from the bar.
+    pub fn remove_item(&mut self, idx: usize) -> Result<(), Box<dyn Error>> {
+        let len = self.config.items.len();
+        if idx >= len {
+            bail!("no item found with index: {}", idx);
+        }
+
+        let mut items = self.config.items.clone();
+        items.remove(idx);
+        let new_config = self.config.with_items(items)?;
+
+        let reused = (0..len).filter(|i| *i != idx).map(Some).collect();
+        self.update(new_config, reused)?;
+        log::info!("removed item[{}]", idx);
+
+        Ok(())
+    }
+
+    /// Replace the configuration of the item at the given index, and restart it.
+    pub fn replace_item(&mut self, idx: usize, item: Item) -> Result<(), Box<dyn Error>> {
+        let len = self.config.items.len();
+        if idx >= len {
+            bail!("no item found with index: {}", idx);
+        }
+
+        let mut items = self.config.items.clone();
+        items[idx] = item;
+        let new_config = self.config.with_items(items)?;
+
+        let reused = (0..len).map(|i| (i != idx).then_some(i)).collect();
+        self.update(new_config, reused)?;
+        log::info!("replaced item[{}]", idx);
+
+        Ok(())
+    }
+
+    /// Replace the configuration, and update the bar to match it. `reused` is the same length as the
+    /// new list of items, and each entry is the index of an item in the old list which should be kept
+    /// running, or `None` if a new item should be started.
+    fn update(
+        &mut self,
+        new_config: AppConfig,
+        reused: Vec<Option<usize>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let old_theme = serde_json::to_value(&self.config.theme)?;
+        let new_theme = serde_json::to_value(&new_config.theme)?;
+        if old_theme != new_theme {
+            self.emit(IpcEvent::Theme(new_theme));
+        }
+
+        let old_ids = std::mem::take(&mut *self.ids);
+        let old_items = std::mem::take(&mut *self.items);
+        let old_dispatcher = std::mem::replace(
+            &mut *self.dispatcher,
+            Dispatcher::new(0, self.config.clone(), self.events.clone()),
+        );
+        let mut old_tasks = self.tasks.drain(..).map(Some).collect::<Vec<_>>();
+
+        // NOTE: the config must be replaced before spawning any new items, since they read from it
+        *self.config = new_config;
+
+        let len = reused.len();
+        let mut ids = Vec::with_capacity(len);
+        let mut items = Vec::with_capacity(len);
+        let mut dispatcher = Dispatcher::new(len, self.config.clone(), self.events.clone());
+        let mut tasks = Vec::with_capacity(len);
+        for (idx, old_idx) in reused.into_iter().enumerate() {
+            // items which have finished (e.g., they errored) are started again
+            let old_idx =
+                old_idx.filter(|&i| old_tasks[i].as_ref().map_or(false, |t| !t.is_finished()));
+            match old_idx.and_then(|i| old_tasks[i].take().map(|task| (i, task))) {
+                // this item hasn't changed, so keep it running
+                Some((old_idx, task)) => {
+                    ids.push(old_ids[old_idx]);
+                    items.push(old_items[old_idx].clone().instance(idx.to_string()));
+                    if let Some(tx) = old_dispatcher.get(old_idx) {
+                        dispatcher.set(idx, tx.clone());
+                    }
+                    tasks.push(task);
+                }
+                // this item is new or has changed, so start it
+                None => {
+                    let id = self.next_id();
+                    ids.push(id);
+                    items.push(I3Item::empty());
+                    tasks.push(self.spawn_item(id, &self.config.items[idx]));
+                }
+            }
+        }
+
+        // stop any items which are no longer in the bar
+        for task in old_tasks.into_iter().flatten() {
+            task.abort();
+        }
+        self.state.retain_logs(&ids);
+
+        *self.ids = ids;
+        *self.items = items;
+        *self.dispatcher = dispatcher;
+        self.tasks = tasks;
+
+        if *self.running.borrow() {
+            print_bar(&self.config, &self.items, &self.events, &*self.output);
+        }
+
+        Ok(())
+    }
+
+    fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn spawn_item(&self, id: usize, item: &Item) -> JoinHandle<()> {
+        let bar_item = item.to_bar_item();
+
+        // all cheaply cloneable (smart pointers, senders, etc)
+        let mut bar = self.items.clone();
+        let ids = self.ids.clone();
+        let state = self.state.clone();
+        let config = self.config.clone();
+        let item_tx = self.tx.clone();
+        let rx_running = self.running.subscribe();
+        let common = item.common.clone();
+        let events = self.events.clone();
+        let mut dispatcher = self.dispatcher.clone();
+
+        tokio::task::spawn_local(async move {
+            // the updater outlives restarts of the item, so it keeps the item's last update
+            let updater = ItemUpdater::new(item_tx, &common, id);
+            let conditions = ItemUpdater::watch_conditions(updater.clone(), rx_running.clone());
+            tokio::pin!(conditions);
+
+            let mut retries = 0;
+            // returns whether the item finished and stays in the bar
+            let run = async {
+                loop {
+                    // NOTE: always look up the index, since the item may have moved since it was spawned
+                    let idx = match index_of(&ids, id) {
+                        Some(idx) => idx,
+                        None => break false,
+                    };
+
+                    let (event_tx, event_rx) = mpsc::channel(32);
+                    dispatcher.set(idx, event_tx);
+
+                    let ctx = Context::new(
+                        config.clone(),
+                        state.clone(),
+                        updater.clone(),
+                        event_rx,
+                        rx_running.clone(),
+                        common.clone(),
+                        id,
+                    );
+
+                    let result = bar_item.start(ctx).await;
+                    let idx = match index_of(&ids, id) {
+                        Some(idx) => idx,
+                        None => break false,
+                    };
+
+                    match result {
+                        Ok(StopAction::Restart) if retries < 3 => {
+                            log::error!("item[{}] requested restart...", idx);
+                            let _ = events.send(IpcEvent::Restart {
+                                index: idx,
+                                name: config.items[idx].name().clone(),
+                            });
+                            retries += 1;
+                            continue;
+                        }
+                        Ok(StopAction::Restart) => {
+                            log::error!("item[{}] stopped, exceeded max retries", idx);
+                            break false;
+                        }
+                        // since this item has terminated, remove its entry from the bar
+                        action @ Ok(StopAction::Complete) | action @ Ok(StopAction::Remove) => {
+                            log::info!("item[{}] finished running", idx);
+                            dispatcher.remove(idx);
+
+                            // Remove this item if requested
+                            if matches!(action, Ok(StopAction::Remove)) {
+                                // NOTE: wait for all tasks in queue so any remaining item updates are flushed and processed
+                                // before we set it for the last time here
+                                tokio::task::yield_now().await;
+                                // replace with an empty item
+                                if let Some(idx) = index_of(&ids, id) {
+                                    bar[idx] = I3Item::empty();
+                                }
+                            }
+
+                            break matches!(action, Ok(StopAction::Complete));
+                        }
+                        // unexpected error, log and display an error block
+                        Err(e) => {
+                            log::error!("item[{}] exited with error: {}", idx, e);
+                            let _ = events.send(IpcEvent::Error {
+                                index: idx,
+                                name: config.items[idx].name().clone(),
+                                message: e.to_string(),
+                            });
+                            // replace with an error item
+                            let theme = config.theme.clone();
+                            bar[idx] = I3Item::new("ERROR")
+                                .color(theme.bg)
+                                .background_color(theme.red);
+                            break false;
+                        }
+                    }
+                }
+            };
+
+            // keep re-evaluating the item's conditions while it's in the bar, even if it's finished
+            let keep = tokio::select! {
+                keep = run => keep,
+                () = &mut conditions => false,
+            };
+            if keep {
+                conditions.await;
+            }
+        })
+    }
+
+    // task to manage updating the bar and printing it
+    fn handle_item_updates(&self, mut rx: Receiver<(I3Item, usize)>) -> Result<(), Box<dyn Error>> {
+        // some outputs have a header, e.g.: the i3 bar protocol's header
+        if let Some(header) = self.output.header()? {
+            println!("{}", header);
+        }
+
+        let config = self.config.clone();
+        let ids = self.ids.clone();
+        let rx_running = self.running.subscribe();
+        let events = self.events.clone();
+        let output = self.output.clone();
+        let mut bar = self.items.clone();
+        tokio::task::spawn_local(async move {
+            while let Some((i3_item, id)) = rx.recv().await {
+                // the item may have been removed after it sent this update
+                let idx = match index_of(&ids, id) {
+                    Some(idx) => idx,
+                    None => continue,
+                };
+
+                let i3_item = prepare_item(&config, idx, i3_item);
+
+                // don't bother doing anything if the item hasn't changed
+                if bar[idx] == i3_item {
+                    continue;
+                }
+
+                // update item in bar
+                bar[idx] = i3_item;
+
+                // nothing is printed while the bar is stopped, it will be printed when it's continued
+                if !*rx_running.borrow() {
+                    continue;
+                }
+
+                print_bar(&config, &bar, &events, &*output);
+            }
+        });
+
+        Ok(())
+    }
+}
+
+fn index_of(ids: &[usize], id: usize) -> Option<usize> {
+    ids.iter().position(|i| *i == id)
+}
+
+/// Match items from the old configuration to items in the new one. Returns a list the same length as
+/// `new`, where each entry is the index of an identical item in `old` (which can be kept running), or
+/// `None` if the item is new or has changed.
+fn match_items(old: &[Value], new: &[Value]) -> Vec<Option<usize>> {
+    let mut used = vec![false; old.len()];
+    new.iter()
+        .map(|item| {
+            let found = (0..old.len()).find(|&i| !used[i] && old[i] == *item);
+            if let Some(i) = found {
+                used[i] = true;
+            }
+
+            found
+        })
+        .collect()
+}
+
+/// Set the fields of an item which are managed by the bar rather than the item itself.
+fn prepare_item(config: &AppConfig, idx: usize, i3_item: I3Item) -> I3Item {
+    let mut i3_item = i3_item
+        // the name of the item
+        .name(config.items[idx].name())
+        // always override the bar item's `instance`, since we track that ourselves
+        .instance(idx.to_string());
+
+    if let Some(separator) = config.items[idx].common.separator {
+        i3_item = i3_item.separator(separator);
+    }
+
+    i3_item
+}
+
+/// The items as they're shown: either with the powerline theme applied, or with hidden items removed.
+fn build_bar(config: &AppConfig, bar: &[I3Item]) -> Vec<I3Item> {
+    let theme = &config.theme;
+    match theme.powerline_enable {
+        true => create_powerline(bar, config, &make_color_adjuster(&theme.bg, &theme.dim)),
+        // hidden items are removed here, and skipped when creating the powerline bar
+        false => bar.iter().filter(|i| !i.is_hidden()).cloned().collect(),
+    }
+}
+
+/// Print the bar to STDOUT in the configured output format, and send it to any IPC subscribers.
+fn print_bar(
+    config: &AppConfig,
+    bar: &[I3Item],
+    events: &broadcast::Sender<IpcEvent>,
+    output: &dyn Output,
+) {
+    let bar = build_bar(config, bar);
+
+    // only bother creating the event if someone is listening for it
+    if events.receiver_count() > 0 {
+        match serde_json::to_value(&bar) {
+            Ok(value) => {
+                let _ = events.send(IpcEvent::Bar(value));
+            }
+            Err(e) => log::error!("failed to serialise bar event: {}", e),
+        }
+    }
+
+    println!("{}", output.format(&bar));
+}
+
+fn create_powerline<F>(bar: &[I3Item], config: &AppConfig, adjuster: F) -> Vec<I3Item>
+where
+    F: Fn(&HexColor) -> HexColor,
+{
+    let theme = &config.theme;
+    let len = theme.powerline.len();
+    let mut powerline_bar = vec![];
+    let mut powerline_idx = 0;
+    let mut prev_bg = None;
+    for i in 0..bar.len() {
+        let item = &bar[i];
+        if item.full_text.is_empty() || item.is_hidden() {
+            continue;
+        }
+
+        let instance = i.to_string();
+        #[cfg(debug_assertions)]
+        assert_eq!(item.get_instance().unwrap(), &instance);
+
+        // items may override their colours, otherwise the next colours in the theme are used
+        let item_theme = config
+            .items
+            .get(i)
+            .and_then(|item| item.common.theme.as_ref());
+        let c2 = item_theme
+            .and_then(|t| t.powerline.as_ref())
+            .unwrap_or(&theme.powerline[(powerline_idx + 1) % len]);
+        let dim = item_theme.and_then(|t| t.dim).unwrap_or(theme.dim);
+        powerline_idx += 1;
+
+        // create the powerline separator
+        let mut sep_item = I3Item::new(theme.powerline_separator.to_span())
+            .instance(instance)
+            .separator(false)
+            .markup(I3Markup::Pango)
+            .separator_block_width_px(0)
+            .color(c2.bg);
+
+        // the first separator doesn't blend with any other item
+        if let Some(bg) = prev_bg {
+            sep_item = sep_item.background_color(bg);
+        }
+        prev_bg = Some(c2.bg);
+
+        // replace `config.theme.dim` so it's easy to see
+        let adjusted_dim = adjuster(&c2.bg);
+
+        powerline_bar.push(sep_item);
+        powerline_bar.push(
+            item.clone()
+                .full_text(format!(
+                    " {} ",
+                    // replace `config.theme.dim` use in pango spans
+                    item.full_text
+                        .replace(&dim.to_string(), &adjusted_dim.to_string())
+                ))
+                .separator(false)
+                .separator_block_width_px(0)
+                .color(match item.get_color() {
+                    Some(color) if color == &dim => adjusted_dim,
+                    Some(color) => *color,
+                    _ => c2.fg,
+                })
+                .background_color(c2.bg),
+        );
+    }
+    powerline_bar
+}
+
+/// HACK: this assumes that RGB colours scale linearly - I don't know if they do or not.
+/// Used to render the powerline bar and make sure that dim text is visible.
+fn make_color_adjuster(bg: &HexColor, fg: &HexColor) -> impl Fn(&HexColor) -> HexColor {
+    let r = fg.r.abs_diff(bg.r);
+    let g = fg.g.abs_diff(bg.g);
+    let b = fg.b.abs_diff(bg.b);
+    move |c| {
+        HexColor::rgb(
+            r.saturating_add(c.r),
+            g.saturating_add(c.g),
+            b.saturating_add(c.b),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn match_items_unchanged() {
+        let items = [json!({ "type": "cpu" }), json!({ "type": "mem" })];
+        assert_eq!(match_items(&items, &items), [Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn match_items_changed() {
+        let old = [json!({ "type": "cpu" }), json!({ "type": "mem" })];
+        let new = [
+            json!({ "type": "cpu", "interval": "1s" }),
+            json!({ "type": "mem" }),
+        ];
+        assert_eq!(match_items(&old, &new), [None, Some(1)]);
+    }
+
+    #[test]
+    fn match_items_moved() {
+        let old = [json!({ "type": "cpu" }), json!({ "type": "mem" })];
+        let new = [
+            json!({ "type": "disk" }),
+            json!({ "type": "mem" }),
+            json!({ "type": "cpu" }),
+        ];
+        assert_eq!(match_items(&old, &new), [None, Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn match_items_duplicates() {
+        let old = [json!({ "type": "raw" }), json!({ "type": "raw" })];
+        let new = [
+            json!({ "type": "raw" }),
+            json!({ "type": "raw" }),
+            json!({ "type": "raw" }),
+        ];
+        assert_eq!(match_items(&old, &new), [Some(0), Some(1), None]);
+    }
+
+    #[test]
+    fn match_items_removed() {
+        let old = [json!({ "type": "cpu" }), json!({ "type": "mem" })];
+        let new = [json!({ "type": "mem" })];
+        assert_eq!(match_items(&old, &new), [Some(1)]);
+    }
+}
@@ -0,0 +1,275 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Parser;
+use nix::sys::inotify::AddWatchFlags;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::fs::{self, read_to_string};
+
+use crate::context::{BarEvent, BarItem, Context, CustomResponse, StopAction};
+use crate::dbus::login1::SessionProxy;
+use crate::dbus::{dbus_connection, BusType};
+use crate::i3::{I3Button, I3Item};
+use crate::util::ffi::AcpiGenericNetlinkEvent;
+use crate::util::{inotify_watch, netlink_acpi_listen, FloatFormat, Placeholders};
+
+#[derive(Debug, Parser)]
+#[command(name = "backlight", no_binary_name = true)]
+enum BacklightCommand {
+    Info,
+    Up,
+    Down,
+    Set { pct: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "detail")]
+pub enum BacklightResponse {
+    Info(Value),
+    Success,
+    Failure(String),
+}
+
+#[derive(Debug, Clone)]
+struct Device(PathBuf);
+
+impl Device {
+    const SYSFS_DIR: &str = "/sys/class/backlight";
+
+    async fn find(name: Option<&str>) -> Result<Device, Box<dyn Error>> {
+        if let Some(name) = name {
+            return Ok(Device(PathBuf::from(Self::SYSFS_DIR).join(name)));
+        }
+
+        let mut entries = fs::read_dir(Self::SYSFS_DIR).await?;
+        match entries.next_entry().await? {
+            Some(entry) => Ok(Device(entry.path())),
+            None => bail!("no backlight devices found"),
+        }
+    }
+
+    fn name(&self) -> String {
+        self.0
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    async fn read_u32(&self, file_name: &str) -> Result<u32, Box<dyn Error>> {
+        Ok(read_to_string(self.0.join(file_name))
+            .await?
+            .trim()
+            .parse::<u32>()?)
+    }
+
+    /// The current brightness as a percentage.
+    async fn percent(&self) -> Result<f64, Box<dyn Error>> {
+        let brightness = self.read_u32("brightness").await?;
+        let max = self.read_u32("max_brightness").await?;
+        Ok(brightness_to_percent(brightness, max))
+    }
+
+    /// Set the brightness via logind, so no special permissions are needed to write to sysfs.
+    async fn set_percent(
+        &self,
+        session: &SessionProxy<'_>,
+        pct: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        let max = self.read_u32("max_brightness").await?;
+        session
+            .set_brightness("backlight", &self.name(), percent_to_brightness(pct, max))
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn brightness_to_percent(brightness: u32, max: u32) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+
+    brightness as f64 / max as f64 * 100.0
+}
+
+/// The percentage is clamped, so scrolling past either end stays there.
+fn percent_to_brightness(pct: f64, max: u32) -> u32 {
+    (pct.clamp(0.0, 100.0) / 100.0 * max as f64).round() as u32
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Backlight {
+    /// The name of the device in `/sys/class/backlight`, if not set then the first device is used.
+    device: Option<String>,
+    /// How much the brightness is changed by (as a percentage) when scrolling.
+    #[serde(default = "Backlight::default_increment")]
+    increment: u32,
+}
+
+impl Backlight {
+    fn default_increment() -> u32 {
+        5
+    }
+
+    /// Ensure the configured device exists.
+    pub fn check(&self) -> Vec<String> {
+        match &self.device {
+            Some(name) if !PathBuf::from(Device::SYSFS_DIR).join(name).exists() => {
+                vec![format!("no backlight found with name: {}", name)]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn item(pct: f64) -> I3Item {
+        let icon = match pct as u32 {
+            0..=33 => "󰃞",
+            34..=66 => "󰃟",
+            _ => "󰃠",
+        };
+
+        I3Item::new(format!("{}  {:.0}%", icon, pct)).short_text(format!("{:.0}%", pct))
+    }
+
+    async fn handle_custom_message(
+        &self,
+        device: &Device,
+        session: &SessionProxy<'_>,
+        args: Vec<String>,
+    ) -> CustomResponse {
+        let cmd = match BacklightCommand::try_parse_from(args) {
+            Ok(cmd) => cmd,
+            Err(e) => return CustomResponse::Help(e.render()),
+        };
+
+        let pct = match device.percent().await {
+            Ok(pct) => pct,
+            Err(e) => {
+                return CustomResponse::Json(json!(BacklightResponse::Failure(e.to_string())))
+            }
+        };
+
+        let result = match cmd {
+            BacklightCommand::Info => {
+                return CustomResponse::Json(json!(BacklightResponse::Info(json!({
+                    "name": device.name(),
+                    "brightness": pct.round(),
+                }))))
+            }
+            BacklightCommand::Up => {
+                device
+                    .set_percent(session, pct + self.increment as f64)
+                    .await
+            }
+            BacklightCommand::Down => {
+                device
+                    .set_percent(session, pct - self.increment as f64)
+                    .await
+            }
+            BacklightCommand::Set { pct } => device.set_percent(session, pct as f64).await,
+        };
+
+        CustomResponse::Json(json!(match result {
+            Ok(()) => BacklightResponse::Success,
+            Err(e) => BacklightResponse::Failure(e.to_string()),
+        }))
+    }
+}
+
+#[async_trait(?Send)]
+impl BarItem for Backlight {
+    async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let device = Device::find(self.device.as_deref()).await?;
+        let dbus = dbus_connection(BusType::System).await?;
+        let session = SessionProxy::new(&dbus).await?;
+
+        // brightness changes made by other programs are seen by inotify, and changes from brightness
+        // keys are usually seen as acpi video events
+        let mut on_change =
+            inotify_watch(device.0.join("brightness"), AddWatchFlags::IN_MODIFY).await?;
+        // if the acpi netlink family isn't available, then this only refreshes via inotify
+        let mut on_acpi_event = match netlink_acpi_listen().await {
+            Ok(rx) => Some(rx),
+            Err(e) => {
+                log::warn!(
+                    "failed to listen for acpi events, only refreshing on brightness changes: {}",
+                    e
+                );
+                None
+            }
+        };
+        loop {
+            let pct = device.percent().await?;
+            let placeholders = Placeholders::new()
+                .float("pct", pct, &FloatFormat::default())
+                .text("name", device.name());
+            ctx.update_item_with(Self::item(pct), placeholders).await?;
+
+            // wait for something which may have changed the brightness
+            loop {
+                tokio::select! {
+                    Some(event) = ctx.raw_event_rx().recv() => {
+                        match event {
+                            BarEvent::Custom { payload, responder } => {
+                                let _ = responder.send(self.handle_custom_message(&device, &session, payload).await);
+                            }
+                            BarEvent::Click(click) => {
+                                let change = match click.button {
+                                    I3Button::ScrollUp => Some(self.increment as f64),
+                                    I3Button::ScrollDown => Some(-(self.increment as f64)),
+                                    _ => None,
+                                };
+
+                                if let Some(change) = change {
+                                    if let Err(e) = device.set_percent(&session, pct + change).await {
+                                        log::error!("failed to set brightness: {}", e);
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                        break;
+                    }
+                    Some(()) = on_change.recv() => break,
+                    // other acpi events (e.g., the battery or AC adapter) don't change the brightness
+                    Some(event) = async { on_acpi_event.as_mut()?.recv().await } => {
+                        if event.device_class == AcpiGenericNetlinkEvent::DEVICE_CLASS_VIDEO {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brightness_percent() {
+        assert_eq!(brightness_to_percent(0, 255), 0.0);
+        assert_eq!(brightness_to_percent(50, 200), 25.0);
+        assert_eq!(brightness_to_percent(255, 255), 100.0);
+        assert_eq!(brightness_to_percent(10, 0), 0.0);
+
+        assert_eq!(percent_to_brightness(0.0, 255), 0);
+        assert_eq!(percent_to_brightness(25.0, 200), 50);
+        assert_eq!(percent_to_brightness(100.0, 255), 255);
+        // rounds to the nearest step
+        assert_eq!(percent_to_brightness(50.0, 7), 4);
+        // clamped when scrolling past either end
+        assert_eq!(percent_to_brightness(-5.0, 255), 0);
+        assert_eq!(percent_to_brightness(105.0, 255), 255);
+
+        // a round trip through both keeps the brightness
+        for brightness in 0..=120 {
+            let pct = brightness_to_percent(brightness, 120);
+            assert_eq!(percent_to_brightness(pct, 120), brightness);
+        }
+    }
+}
@@ -1,23 +1,39 @@
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::try_join;
+use futures::future::join_all;
+use futures::{try_join, StreamExt};
 use hex_color::HexColor;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use tokio::fs::{self, read_to_string};
 use tokio::sync::mpsc::Receiver;
+use zbus::{CacheProperties, MatchRule, MessageStream, MessageType};
 
+use crate::config::schema::DurationSchema;
 use crate::context::{BarEvent, BarItem, Context, StopAction};
 use crate::dbus::notifications::NotificationsProxy;
+use crate::dbus::upower::{device_kind, device_state, DeviceProxy, UPowerProxy, UPOWER_SERVICE};
 use crate::dbus::{dbus_connection, BusType};
 use crate::i3::{I3Button, I3Item, I3Markup};
-use crate::theme::Theme;
+use crate::theme::{Theme, ThemeColor};
 use crate::util::ffi::AcpiGenericNetlinkEvent;
-use crate::util::{netlink_acpi_listen, Paginator};
+use crate::util::{
+    exec_with_env,
+    netlink_acpi_listen,
+    FloatFormat,
+    Paginator,
+    Placeholders,
+    Threshold,
+    Thresholds,
+};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 enum BatState {
     Unknown,
     Charging,
@@ -27,6 +43,16 @@ enum BatState {
 }
 
 impl BatState {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Charging => "charging",
+            Self::Discharging => "discharging",
+            Self::NotCharging => "not_charging",
+            Self::Full => "full",
+        }
+    }
+
     fn get_color(&self, theme: &Theme) -> (Option<&str>, Option<HexColor>) {
         match self {
             Self::Full => (None, Some(theme.purple)),
@@ -51,7 +77,226 @@ impl FromStr for BatState {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How far (as a percentage) the charge must move back past a hook's percentage before the hook can
+/// fire again, so it doesn't fire repeatedly when the charge fluctuates around it.
+const HOOK_HYSTERESIS: f64 = 2.0;
+
+/// Runs a command and/or sends a notification, once each time its condition becomes true.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatteryHook {
+    /// Fires when the charge drops to this percentage, or rises to it when `state` is `charging` or
+    /// `full`. If unset then only `state` is checked.
+    percent: Option<f64>,
+    /// Only fire in this state, if unset then any state.
+    state: Option<BatState>,
+    /// A command to run, `BATTERY_PERCENT` and `BATTERY_STATE` are set in its environment.
+    command: Option<String>,
+    /// The text of a desktop notification to send.
+    notify: Option<String>,
+}
+
+impl BatteryHook {
+    fn rising(&self) -> bool {
+        matches!(self.state, Some(BatState::Charging | BatState::Full))
+    }
+
+    /// Whether the hook's condition is true, with the percentage extended by `margin` past the
+    /// threshold.
+    fn matches(&self, sample: &Sample, margin: f64) -> bool {
+        let pct = sample.percent();
+        self.state.map_or(true, |state| state == sample.state)
+            && self.percent.map_or(true, |threshold| {
+                if self.rising() {
+                    pct >= threshold - margin
+                } else {
+                    pct <= threshold + margin
+                }
+            })
+    }
+
+    async fn fire(&self, sample: &Sample, notifications: &NotificationsProxy<'_>) {
+        let pct = format!("{:.0}", sample.percent());
+        if let Some(command) = self.command.clone() {
+            let env = HashMap::from([
+                ("BATTERY_PERCENT", pct.clone()),
+                ("BATTERY_STATE", sample.state.name().to_string()),
+            ]);
+            // don't block the item while it runs
+            tokio::task::spawn_local(async move { exec_with_env(command, env).await });
+        }
+
+        if let Some(text) = &self.notify {
+            let critical = sample.state == BatState::Discharging;
+            notifications.battery(&pct, text, critical).await;
+        }
+    }
+}
+
+/// Find which hooks should fire for the sample: those whose condition has just become true. Each
+/// hook's entry in `fired` is set until its condition is false again (beyond the hysteresis).
+/// The first sample only sets `fired`, so conditions which are already true (e.g., when starting at
+/// a low charge, or after a reload) don't fire.
+fn update_hooks(
+    hooks: &[BatteryHook],
+    fired: &mut Option<Vec<bool>>,
+    sample: &Sample,
+) -> Vec<usize> {
+    let fired = match fired {
+        Some(fired) => fired,
+        None => {
+            *fired = Some(hooks.iter().map(|h| h.matches(sample, 0.0)).collect());
+            return vec![];
+        }
+    };
+
+    let mut to_fire = vec![];
+    for (idx, hook) in hooks.iter().enumerate() {
+        if fired[idx] {
+            fired[idx] = hook.matches(sample, HOOK_HYSTERESIS);
+        } else if hook.matches(sample, 0.0) {
+            fired[idx] = true;
+            to_fire.push(idx);
+        }
+    }
+
+    to_fire
+}
+
+/// How many samples of a battery's rate of (dis)charge are averaged when estimating the time until
+/// it's empty or full.
+const RATE_SAMPLES: usize = 8;
+
+/// A reading of a battery's state.
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    state: BatState,
+    /// The current level, in µWh if `energy` is set or µAh otherwise
+    now: f64,
+    /// The level when full, in the same unit as `now`
+    full: f64,
+    /// Whether the levels are energy (µWh) rather than charge (µAh)
+    energy: bool,
+    /// The rate of (dis)charge, in µW or µA to match the levels
+    rate: Option<f64>,
+    watts: Option<f64>,
+    /// Used to convert charge to energy, when combining batteries which report different units
+    voltage: Option<f64>,
+    /// The time until empty (or full), if the source estimates it itself
+    time: Option<Duration>,
+    /// Whether the battery powers the system, rather than a peripheral (e.g., a mouse)
+    power_supply: bool,
+}
+
+impl Sample {
+    fn percent(&self) -> f64 {
+        self.now / self.full * 100.0
+    }
+
+    /// Convert charge to energy, if the voltage is unknown then this is left as it is.
+    fn to_energy(&self) -> Sample {
+        match (self.energy, self.voltage) {
+            (false, Some(voltage)) => {
+                let convert = |n: f64| n * voltage / 1_000_000.0;
+                Sample {
+                    now: convert(self.now),
+                    full: convert(self.full),
+                    energy: true,
+                    rate: self.rate.map(convert),
+                    ..self.clone()
+                }
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Combine multiple batteries into one, as if they were a single battery.
+    fn aggregate(samples: &[Sample]) -> Sample {
+        let same_unit = samples.windows(2).all(|w| w[0].energy == w[1].energy);
+        let samples = samples
+            .iter()
+            .map(|s| if same_unit { s.clone() } else { s.to_energy() })
+            .collect::<Vec<_>>();
+
+        let has = |state: BatState| samples.iter().any(|s| s.state == state);
+        let state = if has(BatState::Charging) {
+            BatState::Charging
+        } else if has(BatState::Discharging) {
+            BatState::Discharging
+        } else if samples.iter().all(|s| s.state == BatState::Full) {
+            BatState::Full
+        } else {
+            samples.first().map_or(BatState::Unknown, |s| s.state)
+        };
+
+        // only known if it's known for all of them
+        let sum = |f: fn(&Sample) -> Option<f64>| samples.iter().map(f).sum::<Option<f64>>();
+
+        Sample {
+            state,
+            now: samples.iter().map(|s| s.now).sum(),
+            full: samples.iter().map(|s| s.full).sum(),
+            energy: samples.iter().all(|s| s.energy),
+            rate: sum(|s| s.rate),
+            watts: sum(|s| s.watts),
+            voltage: None,
+            time: None,
+            power_supply: true,
+        }
+    }
+}
+
+/// Keeps recent samples of the rate of (dis)charge, to smooth out the estimated time remaining.
+#[derive(Debug, Default)]
+struct RateHistory {
+    state: Option<BatState>,
+    rates: VecDeque<f64>,
+}
+
+impl RateHistory {
+    /// Add the sample's rate, and return the average of the recent rates.
+    fn update(&mut self, sample: &Sample) -> Option<f64> {
+        // the rate is quite different when charging vs discharging, so start again when it changes
+        if self.state != Some(sample.state) {
+            self.state = Some(sample.state);
+            self.rates.clear();
+        }
+
+        if let Some(rate) = sample.rate.filter(|rate| *rate > 0.0) {
+            if self.rates.len() == RATE_SAMPLES {
+                self.rates.pop_front();
+            }
+            self.rates.push_back(rate);
+        }
+
+        if self.rates.is_empty() {
+            None
+        } else {
+            Some(self.rates.iter().sum::<f64>() / self.rates.len() as f64)
+        }
+    }
+}
+
+/// The estimated time until the battery is empty (if discharging) or full (if charging).
+fn time_remaining(sample: &Sample, rate: Option<f64>) -> Option<Duration> {
+    let rate = rate.filter(|rate| *rate > 0.0)?;
+    let remaining = match sample.state {
+        BatState::Discharging => sample.now,
+        BatState::Charging => sample.full - sample.now,
+        _ => return None,
+    };
+
+    // levels are per hour, e.g.: µWh and µW
+    Some(Duration::from_secs_f64(
+        (remaining / rate).max(0.0) * 3600.0,
+    ))
+}
+
+fn format_time(duration: Duration) -> String {
+    let mins = duration.as_secs() / 60;
+    format!("{}:{:02}", mins / 60, mins % 60)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct Bat(PathBuf);
 
 impl Bat {
@@ -63,6 +308,13 @@ impl Bat {
         Ok(self.read(file_name).await?.trim().parse::<usize>()?)
     }
 
+    /// Read an optional value, some drivers report negative currents when discharging so the
+    /// absolute value is used.
+    async fn read_optional(&self, file_name: impl AsRef<str>) -> Option<f64> {
+        let value = self.read(file_name).await.ok()?;
+        value.trim().parse::<f64>().ok().map(f64::abs)
+    }
+
     fn name(&self) -> Result<String, Box<dyn Error>> {
         match self.0.file_name() {
             Some(name) => Ok(name.to_string_lossy().into_owned()),
@@ -74,67 +326,47 @@ impl Bat {
         Ok(BatState::from_str(self.read("status").await?.trim())?)
     }
 
+    /// Batteries either report their charge (`charge_*` in µAh) or their energy (`energy_*` in µWh).
     // NOTE: there is also `/capacity` which returns an integer percentage
-    async fn percent(&self) -> Result<f32, Box<dyn Error>> {
-        let (charge_now, charge_full) = try_join!(
+    async fn levels(&self) -> Result<(f64, f64, bool), Box<dyn Error>> {
+        if let Ok((now, full)) = try_join!(
+            self.read_usize("energy_now"),
+            self.read_usize("energy_full")
+        ) {
+            return Ok((now as f64, full as f64, true));
+        }
+
+        let (now, full) = try_join!(
             self.read_usize("charge_now"),
             self.read_usize("charge_full"),
         )?;
-        Ok((charge_now as f32) / (charge_full as f32) * 100.0)
-    }
-
-    async fn watts_now(&self) -> Result<f64, Box<dyn Error>> {
-        let (current_pico, voltage_pico) = try_join!(
-            self.read_usize("current_now"),
-            self.read_usize("voltage_now"),
-        )?;
-        Ok((current_pico as f64) * (voltage_pico as f64) / 1_000_000_000_000.0)
+        Ok((now as f64, full as f64, false))
     }
 
-    async fn format(&self, theme: &Theme, show_watts: bool) -> Result<I3Item, Box<dyn Error>> {
-        let (charge, state) = match try_join!(self.percent(), self.get_state()) {
-            Ok((charge, state)) => (charge, state),
-            // Return unknown state: the files in sysfs aren't present at times, such as when connecting
-            // ac adapters, etc. In these scenarios we just end early here without an error and let the
-            // item retry on the next interval/acpi event.
-            Err(e) => {
-                log::warn!("failed to read battery {}: {}", self.0.display(), e);
-                return Ok(I3Item::new("???").color(theme.red));
-            }
-        };
-
-        let (charge_icon, charge_fg, urgent) = match charge as u32 {
-            0..=15 => {
-                let urgent = !matches!(state, BatState::Charging | BatState::NotCharging);
-                ("", Some(theme.red), urgent)
-            }
-            16..=25 => ("", Some(theme.orange), false),
-            26..=50 => ("", Some(theme.yellow), false),
-            51..=75 => ("", None, false),
-            76..=u32::MAX => ("", Some(theme.green), false),
-        };
-
-        let (state_icon, state_fg) = state.get_color(theme);
-        let icon = state_icon.unwrap_or(charge_icon);
-        let fg = state_fg.or(charge_fg);
+    async fn sample(&self) -> Result<Sample, Box<dyn Error>> {
+        let ((now, full, energy), state) = try_join!(self.levels(), self.get_state())?;
+        let power = self.read_optional("power_now").await;
+        let current = self.read_optional("current_now").await;
+        let voltage = self.read_optional("voltage_now").await;
 
-        let item = if show_watts {
-            let watts = self.watts_now().await?;
-            I3Item::new(format!("{:.2} W", watts)).short_text(format!("{:.0}", watts))
+        // µW from either `power_now`, or from `current_now` (µA) and `voltage_now` (µV)
+        let power = power.or_else(|| Some(current? * voltage? / 1_000_000.0));
+        let rate = if energy {
+            power
         } else {
-            let name = self.name()?;
-            let name = if name == "BAT0" {
-                icon
-            } else {
-                name.as_str().into()
-            };
-            I3Item::new(format!("{}  {:.0}%", name, charge)).short_text(format!("{:.0}%", charge))
+            current.or_else(|| Some(power? * 1_000_000.0 / voltage?))
         };
 
-        Ok(match (urgent, fg) {
-            (true, _) => item.urgent(true),
-            (false, Some(fg)) => item.color(fg),
-            (false, None) => item,
+        Ok(Sample {
+            state,
+            now,
+            full,
+            energy,
+            rate,
+            watts: power.map(|p| p / 1_000_000.0),
+            voltage: self.read_optional("voltage_min_design").await.or(voltage),
+            time: None,
+            power_supply: true,
         })
     }
 
@@ -146,7 +378,9 @@ impl Bat {
         while let Some(entry) = entries.next_entry().await? {
             if entry.file_type().await?.is_symlink() {
                 let path = entry.path();
-                if fs::try_exists(path.join("charge_now")).await? {
+                if fs::try_exists(path.join("charge_now")).await?
+                    || fs::try_exists(path.join("energy_now")).await?
+                {
                     batteries.push(Bat(path));
                 }
             }
@@ -156,69 +390,469 @@ impl Bat {
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// Format a battery (or all batteries combined, when `name` is `None`).
+fn format_sample(
+    sample: &Sample,
+    name: Option<&str>,
+    rate: Option<f64>,
+    theme: &Theme,
+    thresholds: &Thresholds,
+    show_watts: bool,
+) -> (I3Item, Placeholders) {
+    let charge = sample.percent();
+    let state = sample.state;
+
+    let charge_icon = match charge as u32 {
+        0..=15 => "",
+        16..=25 => "",
+        26..=50 => "",
+        51..=75 => "",
+        76..=u32::MAX => "",
+    };
+
+    // don't mark the item as urgent if it's plugged in
+    let threshold = thresholds.find(&charge);
+    let charge_fg = threshold.and_then(|t| t.get_color(theme));
+    let urgent = threshold.map_or(false, |t| t.urgent)
+        && !matches!(state, BatState::Charging | BatState::NotCharging);
+
+    let (state_icon, state_fg) = state.get_color(theme);
+    let icon = state_icon.unwrap_or(charge_icon);
+    let fg = state_fg.or(charge_fg);
+
+    let time = sample
+        .time
+        .or_else(|| time_remaining(sample, rate))
+        .map(format_time);
+    let placeholders = Placeholders::new()
+        .float("pct", charge, &FloatFormat::default())
+        .text("icon", icon)
+        .text("name", name.unwrap_or("all"))
+        .text("state", state.name())
+        .text("time", time.as_deref().unwrap_or_default())
+        .text(
+            "watts",
+            sample
+                .watts
+                .map(|watts| format!("{:.2}", watts))
+                .unwrap_or_default(),
+        );
+
+    let item = if show_watts {
+        match sample.watts {
+            Some(watts) => {
+                I3Item::new(format!("{:.2} W", watts)).short_text(format!("{:.0}", watts))
+            }
+            None => I3Item::new("? W").short_text("?"),
+        }
+    } else {
+        let name = match name {
+            Some(name) if name != "BAT0" => name,
+            _ => icon,
+        };
+        let time = time.map(|time| format!(" {}", time)).unwrap_or_default();
+        I3Item::new(format!("{}  {:.0}%{}", name, charge, time))
+            .short_text(format!("{:.0}%", charge))
+    };
+
+    let item = match (urgent, fg) {
+        (true, _) => item.urgent(true),
+        (false, Some(fg)) => item.color(fg),
+        (false, None) => item,
+    };
+
+    (item, placeholders)
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatterySource {
+    /// Read batteries from `/sys/class/power_supply`, refreshing on an interval and on ACPI events.
+    #[default]
+    Sysfs,
+    /// Read batteries (including peripherals, such as mice or headsets) from UPower over D-Bus,
+    /// refreshing whenever they change.
+    Upower,
+}
+
+/// Where batteries are read from, see `BatterySource`.
+enum Source {
+    Sysfs {
+        batteries: Vec<Bat>,
+        names: Vec<String>,
+        acpi_events: Receiver<BatteryAcpiEvent>,
+    },
+    Upower {
+        upower: UPowerProxy<'static>,
+        changes: MessageStream,
+        on_battery: bool,
+    },
+}
+
+impl Source {
+    async fn new(battery: &Battery) -> Result<Source, Box<dyn Error>> {
+        match battery.source {
+            BatterySource::Sysfs => {
+                let batteries = match battery.batteries.clone() {
+                    Some(inner) => inner,
+                    None => Bat::find_all().await?,
+                };
+
+                if batteries.is_empty() {
+                    bail!("no batteries found");
+                }
+
+                let names = batteries
+                    .iter()
+                    .map(|bat| bat.name())
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(Source::Sysfs {
+                    batteries,
+                    names,
+                    acpi_events: battery_acpi_events().await?,
+                })
+            }
+            BatterySource::Upower => {
+                let connection = dbus_connection(BusType::System).await?;
+                let upower = UPowerProxy::builder(connection)
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?;
+
+                // any signal from upower: devices being added, removed or changed
+                let rule = MatchRule::builder()
+                    .msg_type(MessageType::Signal)
+                    .sender(UPOWER_SERVICE)?
+                    .build();
+                let changes = MessageStream::for_match_rule(rule, connection, None).await?;
+
+                Ok(Source::Upower {
+                    on_battery: upower.on_battery().await?,
+                    upower,
+                    changes,
+                })
+            }
+        }
+    }
+
+    /// Read all batteries (so the history of each of them is kept up to date), along with their
+    /// names. Batteries which couldn't be read have no sample.
+    async fn read(&self) -> Result<Vec<(String, Option<Sample>)>, Box<dyn Error>> {
+        match self {
+            Source::Sysfs {
+                batteries, names, ..
+            } => {
+                let samples = join_all(batteries.iter().map(|bat| async move {
+                    match bat.sample().await {
+                        Ok(sample) => Some(sample),
+                        // The files in sysfs aren't present at times, such as when connecting ac
+                        // adapters, etc. In these scenarios we don't return an error, and let the
+                        // item retry on the next interval/acpi event.
+                        Err(e) => {
+                            log::warn!("failed to read battery {}: {}", bat.0.display(), e);
+                            None
+                        }
+                    }
+                }))
+                .await;
+
+                Ok(names.iter().cloned().zip(samples).collect())
+            }
+            Source::Upower { upower, .. } => {
+                let mut batteries = vec![];
+                for path in upower.enumerate_devices().await? {
+                    let device = DeviceProxy::builder(upower.connection())
+                        .path(path.clone())?
+                        .cache_properties(CacheProperties::No)
+                        .build()
+                        .await?;
+
+                    // devices may be removed while they're being read, so they're skipped
+                    match upower_sample(&device).await {
+                        Ok(Some(battery)) => batteries.push(battery),
+                        Ok(None) => {}
+                        Err(e) => log::warn!("failed to read device {}: {}", path.as_str(), e),
+                    }
+                }
+
+                Ok(batteries)
+            }
+        }
+    }
+
+    /// Wait until the batteries may have changed. Returns whether the ac adapter was just plugged in
+    /// or unplugged, if it was.
+    async fn wait_for_change(&mut self) -> Result<Option<bool>, Box<dyn Error>> {
+        match self {
+            Source::Sysfs { acpi_events, .. } => match acpi_events.recv().await {
+                Some(BatteryAcpiEvent::AcAdapterPlugged(plugged_in)) => Ok(Some(plugged_in)),
+                Some(BatteryAcpiEvent::Battery) => Ok(None),
+                // the failure was already logged, so only refresh on the interval from now on
+                None => futures::future::pending().await,
+            },
+            Source::Upower {
+                upower,
+                changes,
+                on_battery,
+            } => {
+                if changes.next().await.is_none() {
+                    bail!("upower signal stream ended");
+                }
+
+                let was_on_battery = std::mem::replace(on_battery, upower.on_battery().await?);
+                Ok((was_on_battery != *on_battery).then_some(!*on_battery))
+            }
+        }
+    }
+}
+
+/// Read a device from UPower, returns `None` if it's not a battery.
+async fn upower_sample(
+    device: &DeviceProxy<'_>,
+) -> Result<Option<(String, Sample)>, Box<dyn Error>> {
+    if matches!(
+        device.kind().await?,
+        device_kind::UNKNOWN | device_kind::LINE_POWER
+    ) {
+        return Ok(None);
+    }
+
+    let (native_path, model, power_supply, percentage, state) = try_join!(
+        device.native_path(),
+        device.model(),
+        device.power_supply(),
+        device.percentage(),
+        device.state(),
+    )?;
+    let (energy, energy_full, energy_rate, time_to_empty, time_to_full) = try_join!(
+        device.energy(),
+        device.energy_full(),
+        device.energy_rate(),
+        device.time_to_empty(),
+        device.time_to_full(),
+    )?;
+
+    let state = match state {
+        device_state::CHARGING => BatState::Charging,
+        device_state::DISCHARGING | device_state::EMPTY => BatState::Discharging,
+        device_state::FULLY_CHARGED => BatState::Full,
+        device_state::PENDING_CHARGE | device_state::PENDING_DISCHARGE => BatState::NotCharging,
+        _ => BatState::Unknown,
+    };
+
+    let time = match state {
+        BatState::Discharging => time_to_empty,
+        BatState::Charging => time_to_full,
+        _ => 0,
+    };
+
+    // peripherals are named by their model, system batteries like they are in sysfs (e.g., `BAT0`)
+    let name = if power_supply || model.is_empty() {
+        native_path
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        model
+    };
+
+    // peripherals usually only report a percentage
+    let (now, full, rate, watts) = if energy_full > 0.0 {
+        (
+            energy * 1_000_000.0,
+            energy_full * 1_000_000.0,
+            Some(energy_rate * 1_000_000.0),
+            Some(energy_rate),
+        )
+    } else {
+        (percentage, 100.0, None, None)
+    };
+
+    let sample = Sample {
+        state,
+        now,
+        full,
+        energy: energy_full > 0.0,
+        rate,
+        watts,
+        voltage: None,
+        time: (time > 0).then(|| Duration::from_secs(time as u64)),
+        power_supply,
+    };
+
+    Ok(Some((name, sample)))
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Battery {
+    /// How often to refresh, only used when the source is `sysfs`.
     #[serde(with = "crate::human_time")]
+    #[schemars(with = "DurationSchema")]
     interval: Duration,
+    #[serde(default)]
+    source: BatterySource,
+    /// Only used when the source is `sysfs`, if unset then all batteries are found.
     batteries: Option<Vec<Bat>>,
     #[serde(default)]
     notify_on_adapter: bool,
-    // TODO: option to run command(s) at certain percentage(s)
+    /// Thresholds for the battery's charge percentage. Urgent thresholds are ignored while charging.
+    #[serde(default = "Battery::default_thresholds")]
+    thresholds: Thresholds,
+    /// Actions to take when the battery reaches a certain percentage or state.
+    #[serde(default)]
+    hooks: Vec<BatteryHook>,
+}
+
+impl Battery {
+    fn default_thresholds() -> Thresholds {
+        Thresholds::new(vec![
+            Threshold::new(0.0, Some(ThemeColor::Red)).urgent(true),
+            Threshold::new(16.0, Some(ThemeColor::Orange)),
+            Threshold::new(26.0, Some(ThemeColor::Yellow)),
+            Threshold::new(51.0, None),
+            Threshold::new(76.0, Some(ThemeColor::Green)),
+        ])
+        .unwrap()
+    }
+
+    /// Ensure any configured batteries exist.
+    pub fn check(&self) -> Vec<String> {
+        let batteries = self
+            .batteries
+            .iter()
+            .flatten()
+            .filter(|bat| !bat.0.join("status").exists())
+            .map(|bat| format!("no battery found at: {}", bat.0.display()));
+
+        let hooks = self.hooks.iter().enumerate().filter_map(|(idx, hook)| {
+            if hook.command.is_none() && hook.notify.is_none() {
+                Some(format!("hook {} has neither a `command` nor `notify`", idx))
+            } else if hook.percent.is_none() && hook.state.is_none() {
+                Some(format!("hook {} has neither a `percent` nor `state`", idx))
+            } else if hook
+                .percent
+                .map_or(false, |pct| !(0.0..=100.0).contains(&pct))
+            {
+                Some(format!("hook {} has a `percent` outside of 0-100", idx))
+            } else {
+                None
+            }
+        });
+
+        let source = (self.source == BatterySource::Upower && self.batteries.is_some())
+            .then(|| "`batteries` is ignored when the source is upower".to_string());
+
+        batteries.chain(hooks).chain(source).collect()
+    }
 }
 
 #[async_trait(?Send)]
 impl BarItem for Battery {
     async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
-        let batteries = match self.batteries.clone() {
-            Some(inner) => inner,
-            None => Bat::find_all().await?,
-        };
+        let mut source = Source::new(self).await?;
 
         let mut show_watts = false;
         let mut p = Paginator::new();
-        if batteries.len() == 0 {
-            bail!("no batteries found");
-        } else {
-            p.set_len(batteries.len());
-        }
+        let mut hooks_fired = None;
+        // keyed by name, `None` is all batteries combined
+        let mut history = HashMap::<Option<String>, RateHistory>::new();
 
         let dbus = dbus_connection(BusType::Session).await?;
         let notifications = NotificationsProxy::new(&dbus).await?;
-        let mut on_acpi_event = battery_acpi_events().await?;
         loop {
-            let theme = &ctx.config.theme;
+            let theme = &ctx.theme();
 
-            let item = batteries[p.idx()].format(theme, show_watts).await?;
-            let full_text = format!("{}{}", item.get_full_text(), p.format(theme));
-            let item = item.full_text(full_text).markup(I3Markup::Pango);
-            ctx.update_item(item).await?;
+            let mut pages = source
+                .read()
+                .await?
+                .into_iter()
+                .map(|(name, sample)| (Some(name), sample))
+                .collect::<Vec<_>>();
 
-            // change delay if we're displaying watts
-            let delay = if show_watts {
-                Duration::from_secs(2)
-            } else {
-                self.interval
-            };
+            // when there are multiple batteries powering the system, the last page shows them all
+            // combined (batteries which can't be read are assumed to power the system)
+            let is_system =
+                |sample: &Option<Sample>| sample.as_ref().map_or(true, |s| s.power_supply);
+            let system = pages.iter().filter(|(_, sample)| is_system(sample)).count();
+            if system > 1 {
+                let readable = pages
+                    .iter()
+                    .filter_map(|(_, sample)| sample.clone())
+                    .filter(|sample| sample.power_supply)
+                    .collect::<Vec<_>>();
+                let combined = (!readable.is_empty()).then(|| Sample::aggregate(&readable));
+                pages.push((None, combined));
+            }
 
-            // cycle though batteries
-            let wait_for_click = ctx.delay_with_event_handler(delay, |event| {
-                p.update(&event);
-                if let BarEvent::Click(click) = event {
-                    if click.button == I3Button::Middle {
-                        show_watts = !show_watts;
-                    }
+            let rates = pages
+                .iter()
+                .map(|(name, sample)| {
+                    let history = history.entry(name.clone()).or_default();
+                    sample.as_ref().and_then(|s| history.update(s))
+                })
+                .collect::<Vec<_>>();
+
+            // hooks use the combined battery if there are multiple, since that's what matters
+            let hooks_sample = match system {
+                1 => pages.iter().find(|(_, sample)| is_system(sample)),
+                _ => pages.last().filter(|(name, _)| name.is_none()),
+            };
+            if let Some((_, Some(sample))) = hooks_sample {
+                for idx in update_hooks(&self.hooks, &mut hooks_fired, sample) {
+                    self.hooks[idx].fire(sample, &notifications).await;
                 }
-                async {}
-            });
+            }
+
+            p.set_len(pages.len().max(1));
+            let idx = p.idx();
+            let (item, placeholders) = match pages.get(idx) {
+                Some((name, Some(sample))) => format_sample(
+                    sample,
+                    name.as_deref(),
+                    rates[idx],
+                    theme,
+                    &self.thresholds,
+                    show_watts,
+                ),
+                Some((_, None)) => (I3Item::new("???").color(theme.red), Placeholders::new()),
+                // only possible with upower, when there aren't any devices
+                None => (I3Item::empty(), Placeholders::new()),
+            };
+
+            let page = p.format(theme);
+            let full_text = format!("{}{}", item.get_full_text(), page);
+            let item = item.full_text(full_text).markup(I3Markup::Pango);
+            ctx.update_item_with(item, placeholders.markup("page", page))
+                .await?;
+
+            // upower sends signals whenever anything changes, otherwise change delay if we're
+            // displaying watts
+            let delay = match (&source, show_watts) {
+                (Source::Upower { .. }, _) => None,
+                (Source::Sysfs { .. }, true) => Some(Duration::from_secs(2)),
+                (Source::Sysfs { .. }, false) => Some(self.interval),
+            };
 
             tokio::select! {
                 // reload block on click (or timeout)
-                () = wait_for_click => {},
-                // reload block on any ACPI event
-                Some(event) = on_acpi_event.recv() => {
-                    if let BatteryAcpiEvent::AcAdapterPlugged(plugged_in) = event {
+                event = ctx.wait_for_event(delay) => {
+                    // cycle though batteries
+                    if let Some(event) = event {
+                        p.update(&event);
+                        if let BarEvent::Click(click) = event {
+                            if click.button == I3Button::Middle {
+                                show_watts = !show_watts;
+                            }
+                        }
+                    }
+                },
+                // reload block on any change to the batteries
+                plugged_in = source.wait_for_change() => {
+                    if let Some(plugged_in) = plugged_in? {
                         if self.notify_on_adapter {
-                            let _ = notifications.ac_adapter(plugged_in).await;
+                            notifications.ac_adapter(plugged_in).await;
                         }
                     }
                 },
@@ -265,3 +899,133 @@ async fn battery_acpi_events() -> Result<Receiver<BatteryAcpiEvent>, Box<dyn Err
 
     Ok(rx)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(state: BatState, now: f64, full: f64, energy: bool, rate: Option<f64>) -> Sample {
+        Sample {
+            state,
+            now,
+            full,
+            energy,
+            rate,
+            watts: None,
+            voltage: Some(10_000_000.0),
+            time: None,
+            power_supply: true,
+        }
+    }
+
+    #[test]
+    fn aggregate() {
+        let combined = Sample::aggregate(&[
+            sample(BatState::Full, 50.0, 50.0, false, None),
+            sample(BatState::Discharging, 10.0, 100.0, false, Some(5.0)),
+        ]);
+        assert_eq!(combined.state, BatState::Discharging);
+        assert_eq!(combined.percent(), 40.0);
+        // not every battery reports a rate
+        assert_eq!(combined.rate, None);
+
+        // charge is converted to energy when units are mixed
+        let combined = Sample::aggregate(&[
+            sample(BatState::Charging, 100.0, 200.0, true, Some(100.0)),
+            sample(BatState::Discharging, 10.0, 20.0, false, Some(10.0)),
+        ]);
+        assert_eq!(combined.state, BatState::Charging);
+        assert_eq!((combined.now, combined.full), (200.0, 400.0));
+        assert_eq!(combined.rate, Some(200.0));
+        assert!(combined.energy);
+    }
+
+    #[test]
+    fn rate_history() {
+        let mut history = RateHistory::default();
+        let discharging = |rate| sample(BatState::Discharging, 50.0, 100.0, true, rate);
+
+        assert_eq!(history.update(&discharging(None)), None);
+        assert_eq!(history.update(&discharging(Some(10.0))), Some(10.0));
+        assert_eq!(history.update(&discharging(Some(20.0))), Some(15.0));
+        for _ in 0..RATE_SAMPLES {
+            history.update(&discharging(Some(30.0)));
+        }
+        assert_eq!(history.update(&discharging(None)), Some(30.0));
+
+        // starts again when the state changes
+        let charging = sample(BatState::Charging, 50.0, 100.0, true, Some(5.0));
+        assert_eq!(history.update(&charging), Some(5.0));
+    }
+
+    #[test]
+    fn hooks() {
+        let hook = |percent, state| BatteryHook {
+            percent,
+            state,
+            command: None,
+            notify: Some("".into()),
+        };
+        let hooks = [
+            hook(Some(15.0), None),
+            hook(Some(5.0), Some(BatState::Discharging)),
+            hook(None, Some(BatState::Full)),
+        ];
+
+        const NONE: [usize; 0] = [];
+        let mut fired = None;
+        let mut update = |state, now| {
+            let s = sample(state, now, 100.0, true, None);
+            update_hooks(&hooks, &mut fired, &s)
+        };
+
+        assert_eq!(update(BatState::Discharging, 20.0), NONE);
+        assert_eq!(update(BatState::Discharging, 15.0), [0]);
+        assert_eq!(update(BatState::Discharging, 14.0), NONE);
+        // not far enough above the percentage to fire again
+        assert_eq!(update(BatState::Discharging, 16.0), NONE);
+        assert_eq!(update(BatState::Discharging, 15.0), NONE);
+        assert_eq!(update(BatState::Discharging, 5.0), [1]);
+        // plugging in doesn't change the charge, but the state no longer matches
+        assert_eq!(update(BatState::Charging, 5.0), NONE);
+        assert_eq!(update(BatState::Discharging, 5.0), [1]);
+        assert_eq!(update(BatState::Charging, 50.0), NONE);
+        assert_eq!(update(BatState::Full, 100.0), [2]);
+        assert_eq!(update(BatState::Full, 100.0), NONE);
+        assert_eq!(update(BatState::Discharging, 15.0), [0]);
+
+        // conditions which are already true for the first sample don't fire
+        let mut fired = None;
+        let mut update = |state, now| {
+            let s = sample(state, now, 100.0, true, None);
+            update_hooks(&hooks, &mut fired, &s)
+        };
+        assert_eq!(update(BatState::Discharging, 10.0), NONE);
+        assert_eq!(update(BatState::Discharging, 9.0), NONE);
+        // but they fire when they're crossed later on
+        assert_eq!(update(BatState::Discharging, 5.0), [1]);
+
+        let mut fired = None;
+        let mut update = |state, now| {
+            let s = sample(state, now, 100.0, true, None);
+            update_hooks(&hooks, &mut fired, &s)
+        };
+        assert_eq!(update(BatState::Full, 100.0), NONE);
+        assert_eq!(update(BatState::Full, 100.0), NONE);
+    }
+
+    #[test]
+    fn time() {
+        let discharging = sample(BatState::Discharging, 30.0, 100.0, true, None);
+        let remaining = time_remaining(&discharging, Some(20.0)).unwrap();
+        assert_eq!(format_time(remaining), "1:30");
+
+        let charging = sample(BatState::Charging, 30.0, 100.0, true, None);
+        let remaining = time_remaining(&charging, Some(7.0)).unwrap();
+        assert_eq!(format_time(remaining), "10:00");
+
+        let full = sample(BatState::Full, 100.0, 100.0, true, None);
+        assert_eq!(time_remaining(&full, Some(1.0)), None);
+        assert_eq!(time_remaining(&discharging, None), None);
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use zbus::fdo::{DBusProxy, ObjectManagerProxy};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{Connection, MatchRule, MessageStream, MessageType};
+
+use crate::context::{BarEvent, BarItem, Context, StopAction};
+use crate::dbus::bluez::{Adapter1Proxy, BLUEZ_SERVICE};
+use crate::dbus::{dbus_connection, BusType};
+use crate::i3::{I3Button, I3Item, I3Markup};
+use crate::theme::Theme;
+use crate::util::{escape, Paginator, Placeholders};
+
+/// The interfaces and properties of each object, as returned by `GetManagedObjects`.
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, HashMap<String, OwnedValue>>>;
+
+#[derive(Debug, PartialEq)]
+struct Device {
+    alias: String,
+    /// Only available for devices which report their battery level
+    battery: Option<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Adapter {
+    path: OwnedObjectPath,
+    powered: bool,
+    /// Only devices which are connected
+    devices: Vec<Device>,
+}
+
+impl Adapter {
+    /// Find the adapter (and its connected devices) from the objects BlueZ manages. If there are
+    /// multiple adapters, the first one is used unless one is named.
+    fn from_objects(objects: &ManagedObjects, name: Option<&str>) -> Option<Adapter> {
+        let mut adapters = objects
+            .iter()
+            .filter(|(path, interfaces)| {
+                interfaces.contains_key("org.bluez.Adapter1")
+                    && name.map_or(true, |name| path.as_str().ends_with(&format!("/{}", name)))
+            })
+            .collect::<Vec<_>>();
+        adapters.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+        let (path, interfaces) = adapters.first()?;
+        let mut devices = objects
+            .iter()
+            .filter_map(|(device_path, interfaces)| {
+                let device = interfaces.get("org.bluez.Device1")?;
+                let adapter = device.get("Adapter").and_then(|v| as_path(v))?;
+                if adapter != path.as_str() || !device.get("Connected").map_or(false, as_bool) {
+                    return None;
+                }
+
+                let alias = device
+                    .get("Alias")
+                    .and_then(|v| as_str(v))
+                    .unwrap_or_else(|| device_path.as_str().into());
+                let battery = interfaces
+                    .get("org.bluez.Battery1")
+                    .and_then(|battery| battery.get("Percentage"))
+                    .and_then(|v| as_u8(v));
+
+                Some((device_path.as_str(), Device { alias, battery }))
+            })
+            .collect::<Vec<_>>();
+        // keep the order stable, so the shown device doesn't change when others connect
+        devices.sort_by(|a, b| a.0.cmp(b.0));
+
+        Some(Adapter {
+            path: (*path).clone(),
+            powered: interfaces["org.bluez.Adapter1"]
+                .get("Powered")
+                .map_or(false, as_bool),
+            devices: devices.into_iter().map(|(_, device)| device).collect(),
+        })
+    }
+}
+
+fn as_bool(value: &OwnedValue) -> bool {
+    matches!(&**value, Value::Bool(true))
+}
+
+fn as_u8(value: &OwnedValue) -> Option<u8> {
+    match &**value {
+        Value::U8(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn as_str(value: &OwnedValue) -> Option<String> {
+    match &**value {
+        Value::Str(s) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+fn as_path(value: &OwnedValue) -> Option<String> {
+    match &**value {
+        Value::ObjectPath(p) => Some(p.to_string()),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Bluetooth {
+    /// The name of the adapter to show (e.g., `hci0`), defaults to the first adapter.
+    adapter: Option<String>,
+}
+
+impl Bluetooth {
+    async fn adapter(
+        &self,
+        objects: &ObjectManagerProxy<'_>,
+    ) -> Result<Option<Adapter>, Box<dyn Error>> {
+        let objects = objects.get_managed_objects().await?;
+        Ok(Adapter::from_objects(&objects, self.adapter.as_deref()))
+    }
+
+    /// Shown when the adapter is off, or when BlueZ isn't running.
+    fn format_powered_off(theme: &Theme) -> (I3Item, Placeholders) {
+        (
+            I3Item::new("󰂲").color(theme.dim),
+            Placeholders::new().text("powered", false),
+        )
+    }
+
+    fn format(adapter: &Adapter, p: &Paginator, theme: &Theme) -> (I3Item, Placeholders) {
+        if !adapter.powered {
+            return Self::format_powered_off(theme);
+        }
+
+        let placeholders = Placeholders::new().text("powered", true);
+
+        let device = match adapter.devices.get(p.idx()) {
+            Some(device) => device,
+            None => return (I3Item::new("󰂯"), placeholders),
+        };
+
+        let battery = device
+            .battery
+            .map(|pct| format!(" {}%", pct))
+            .unwrap_or_default();
+        let page = p.format(theme);
+        let item = I3Item::new(format!("󰂱  {}{}{}", escape(&device.alias), battery, page))
+            .short_text(format!("󰂱{}", battery))
+            .color(theme.blue)
+            .markup(I3Markup::Pango);
+
+        let placeholders = placeholders
+            .text("device", &device.alias)
+            .text(
+                "battery",
+                device
+                    .battery
+                    .map(|pct| pct.to_string())
+                    .unwrap_or_default(),
+            )
+            .markup("page", page);
+
+        (item, placeholders)
+    }
+
+    async fn toggle_power(
+        connection: &Connection,
+        adapter: &Adapter,
+    ) -> Result<(), Box<dyn Error>> {
+        let proxy = Adapter1Proxy::builder(connection)
+            .path(adapter.path.clone())?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?;
+        proxy.set_powered(!adapter.powered).await?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl BarItem for Bluetooth {
+    async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let connection = dbus_connection(BusType::System).await?;
+        let objects = ObjectManagerProxy::builder(connection)
+            .destination(BLUEZ_SERVICE)?
+            .path("/")?
+            .build()
+            .await?;
+
+        // any signal from bluez: adapters or devices being added, removed or changed
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(BLUEZ_SERVICE)?
+            .build();
+        let mut changes = MessageStream::for_match_rule(rule, connection, None).await?;
+        // bluez starting, stopping or restarting
+        let dbus = DBusProxy::new(connection).await?;
+        let mut name_changes = dbus
+            .receive_name_owner_changed_with_args(&[(0, BLUEZ_SERVICE)])
+            .await?;
+
+        let mut p = Paginator::new();
+        loop {
+            let adapter = match self.adapter(&objects).await {
+                Ok(Some(adapter)) => {
+                    p.set_len(adapter.devices.len().max(1));
+                    let (item, placeholders) = Self::format(&adapter, &p, &ctx.theme());
+                    ctx.update_item_with(item, placeholders).await?;
+                    Some(adapter)
+                }
+                Ok(None) => {
+                    ctx.update_item(I3Item::empty()).await?;
+                    None
+                }
+                // bluez isn't running, so wait for it to start
+                Err(e) => {
+                    log::warn!("failed to read bluetooth adapters: {}", e);
+                    let (item, placeholders) = Self::format_powered_off(&ctx.theme());
+                    ctx.update_item_with(item, placeholders).await?;
+                    None
+                }
+            };
+
+            tokio::select! {
+                Some(event) = ctx.raw_event_rx().recv() => match event {
+                    BarEvent::Click(click) if click.button == I3Button::Left => {
+                        if let Some(adapter) = &adapter {
+                            if let Err(e) = Self::toggle_power(connection, adapter).await {
+                                log::error!("failed to toggle bluetooth power: {}", e);
+                            }
+                        }
+                    }
+                    // otherwise, cycle through connected devices
+                    event => p.update(&event),
+                },
+                Some(_) = changes.next() => {}
+                Some(_) = name_changes.next() => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(
+        interfaces: &[(&str, &[(&str, Value)])],
+    ) -> HashMap<String, HashMap<String, OwnedValue>> {
+        interfaces
+            .iter()
+            .map(|(name, props)| {
+                let props = props
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.clone().into()))
+                    .collect();
+                (name.to_string(), props)
+            })
+            .collect()
+    }
+
+    fn path(s: &str) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(s).unwrap()
+    }
+
+    #[test]
+    fn adapter_from_objects() {
+        let adapter_path = Value::ObjectPath("/org/bluez/hci0".try_into().unwrap());
+        let objects: ManagedObjects = HashMap::from([
+            (
+                path("/org/bluez/hci0"),
+                object(&[("org.bluez.Adapter1", &[("Powered", Value::Bool(true))])]),
+            ),
+            (
+                path("/org/bluez/hci0/dev_B"),
+                object(&[
+                    (
+                        "org.bluez.Device1",
+                        &[
+                            ("Alias", Value::from("headphones")),
+                            ("Adapter", adapter_path.clone()),
+                            ("Connected", Value::Bool(true)),
+                        ],
+                    ),
+                    ("org.bluez.Battery1", &[("Percentage", Value::U8(80))]),
+                ]),
+            ),
+            (
+                path("/org/bluez/hci0/dev_A"),
+                object(&[(
+                    "org.bluez.Device1",
+                    &[
+                        ("Alias", Value::from("keyboard")),
+                        ("Adapter", adapter_path.clone()),
+                        ("Connected", Value::Bool(true)),
+                    ],
+                )]),
+            ),
+            (
+                path("/org/bluez/hci0/dev_C"),
+                object(&[(
+                    "org.bluez.Device1",
+                    &[
+                        ("Alias", Value::from("phone")),
+                        ("Adapter", adapter_path),
+                        ("Connected", Value::Bool(false)),
+                    ],
+                )]),
+            ),
+        ]);
+
+        assert_eq!(
+            Adapter::from_objects(&objects, None),
+            Some(Adapter {
+                path: path("/org/bluez/hci0"),
+                powered: true,
+                devices: vec![
+                    Device {
+                        alias: "keyboard".into(),
+                        battery: None,
+                    },
+                    Device {
+                        alias: "headphones".into(),
+                        battery: Some(80),
+                    },
+                ],
+            })
+        );
+
+        assert_eq!(Adapter::from_objects(&objects, Some("hci1")), None);
+    }
+}
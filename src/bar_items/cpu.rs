@@ -2,36 +2,40 @@ use std::error::Error;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use hex_color::HexColor;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use sysinfo::{CpuExt, CpuRefreshKind, SystemExt};
 
+use crate::config::schema::DurationSchema;
 use crate::context::{BarEvent, BarItem, Context, StopAction};
 use crate::i3::{I3Item, I3Markup};
-use crate::theme::Theme;
-use crate::util::exec;
+use crate::theme::{Theme, ThemeColor};
 use crate::util::format::{float, FloatFormat};
+use crate::util::{exec, Placeholders, Threshold, Thresholds};
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Cpu {
     #[serde(with = "crate::human_time")]
+    #[schemars(with = "DurationSchema")]
     interval: Duration,
     #[serde(flatten)]
     float_fmt: FloatFormat,
+    #[serde(default = "Cpu::default_thresholds")]
+    thresholds: Thresholds,
 }
 
 impl Cpu {
-    fn get_full_text(&self, _: &Theme, pct: f32) -> String {
-        format!(" {}%", float(pct, &self.float_fmt))
+    fn default_thresholds() -> Thresholds {
+        Thresholds::new(vec![
+            Threshold::new(40.0, Some(ThemeColor::Yellow)),
+            Threshold::new(60.0, Some(ThemeColor::Orange)),
+            Threshold::new(80.0, Some(ThemeColor::Red)),
+        ])
+        .unwrap()
     }
 
-    fn get_color(&self, theme: &Theme, pct: f32) -> Option<HexColor> {
-        match pct as u64 {
-            80..=100 => Some(theme.red),
-            60..=79 => Some(theme.orange),
-            40..=59 => Some(theme.yellow),
-            _ => None,
-        }
+    fn get_full_text(&self, _: &Theme, pct: f32) -> String {
+        format!(" {}%", float(pct, &self.float_fmt))
     }
 }
 
@@ -48,13 +52,12 @@ impl BarItem for Cpu {
                 ctx.state.sys.global_cpu_info().cpu_usage()
             };
 
-            let theme = &ctx.config.theme;
-            let mut item = I3Item::new(self.get_full_text(theme, pct)).markup(I3Markup::Pango);
-            if let Some(fg) = self.get_color(theme, pct) {
-                item = item.color(fg);
-            }
+            let theme = &ctx.theme();
+            let item = I3Item::new(self.get_full_text(theme, pct)).markup(I3Markup::Pango);
+            let item = self.thresholds.apply(theme, &(pct as f64), item);
 
-            ctx.update_item(item).await?;
+            ctx.update_item_with(item, Placeholders::new().float("pct", pct, &self.float_fmt))
+                .await?;
             ctx.delay_with_event_handler(self.interval, |event| async move {
                 if let BarEvent::Click(_) = event {
                     exec("systemmonitor").await;
@@ -5,21 +5,38 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use bytesize::ByteSize;
-use hex_color::HexColor;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use sysinfo::{Disk as SysDisk, DiskExt, SystemExt};
 
+use crate::config::schema::DurationSchema;
 use crate::context::{BarItem, Context, StopAction};
 use crate::i3::{I3Item, I3Markup};
-use crate::theme::Theme;
-use crate::util::Paginator;
+use crate::theme::{Theme, ThemeColor};
+use crate::util::{FloatFormat, Paginator, Placeholders, Threshold, Thresholds};
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Disk {
     #[serde(with = "crate::human_time")]
+    #[schemars(with = "DurationSchema")]
     interval: Duration,
     #[serde(default)]
     mounts: HashSet<PathBuf>,
+    /// Thresholds for the percentage of available space.
+    #[serde(default = "Disk::default_thresholds")]
+    thresholds: Thresholds,
+}
+
+impl Disk {
+    fn default_thresholds() -> Thresholds {
+        Thresholds::new(vec![
+            Threshold::new(0.0, Some(ThemeColor::Red)),
+            Threshold::new(11.0, Some(ThemeColor::Orange)),
+            Threshold::new(21.0, Some(ThemeColor::Yellow)),
+            Threshold::new(31.0, None),
+        ])
+        .unwrap()
+    }
 }
 
 struct DiskStats {
@@ -37,14 +54,22 @@ impl DiskStats {
         }
     }
 
-    fn get_color(&self, theme: &Theme) -> Option<HexColor> {
-        let pct = (self.available_bytes as f64 / self.total_bytes as f64) * 100.0;
-        match pct as u32 {
-            0..=10 => Some(theme.red),
-            11..=20 => Some(theme.orange),
-            21..=30 => Some(theme.yellow),
-            _ => None,
-        }
+    fn available_pct(&self) -> f64 {
+        (self.available_bytes as f64 / self.total_bytes as f64) * 100.0
+    }
+
+    fn placeholders(&self) -> Placeholders {
+        let used_bytes = self.total_bytes.saturating_sub(self.available_bytes);
+        let used_pct = (used_bytes as f64 / self.total_bytes as f64) * 100.0;
+        Placeholders::new()
+            .text("mount", &self.mount_point)
+            .text(
+                "available",
+                ByteSize(self.available_bytes).to_string_as(true),
+            )
+            .text("used", ByteSize(used_bytes).to_string_as(true))
+            .text("total", ByteSize(self.total_bytes).to_string_as(true))
+            .float("used_pct", used_pct, &FloatFormat::default())
     }
 
     fn format(&self, _: &Theme) -> (String, String) {
@@ -86,17 +111,16 @@ impl BarItem for Disk {
                 p.set_len(len);
 
                 let disk = &stats[p.idx()];
-                let theme = &ctx.config.theme;
+                let theme = &ctx.theme();
                 let (full, short) = disk.format(theme);
-                let full = format!("{}{}", full, p.format(theme));
-
-                let mut item = I3Item::new(full).short_text(short).markup(I3Markup::Pango);
+                let page = p.format(theme);
+                let full = format!("{}{}", full, page);
 
-                if let Some(fg) = disk.get_color(theme) {
-                    item = item.color(fg);
-                }
+                let item = I3Item::new(full).short_text(short).markup(I3Markup::Pango);
+                let item = self.thresholds.apply(theme, &disk.available_pct(), item);
 
-                ctx.update_item(item).await?;
+                ctx.update_item_with(item, disk.placeholders().markup("page", page))
+                    .await?;
             }
 
             // cycle through disks
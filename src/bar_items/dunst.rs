@@ -2,6 +2,7 @@ use std::error::Error;
 
 use async_trait::async_trait;
 use futures::StreamExt;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::context::{BarItem, Context, StopAction};
@@ -10,7 +11,7 @@ use crate::dbus::{dbus_connection, BusType};
 use crate::i3::{I3Item, I3Markup};
 use crate::theme::Theme;
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Dunst {}
 
 impl Dunst {
@@ -33,7 +34,7 @@ impl BarItem for Dunst {
         let connection = dbus_connection(BusType::Session).await?;
         let dunst_proxy = DunstProxy::new(&connection).await?;
         let _ = ctx
-            .update_item(Dunst::item(&ctx.config.theme, dunst_proxy.paused().await?))
+            .update_item(Dunst::item(&ctx.theme(), dunst_proxy.paused().await?))
             .await;
 
         // listen for changes
@@ -42,11 +43,11 @@ impl BarItem for Dunst {
             tokio::select! {
                 Some(change) = stream.next() => {
                     let paused = change.get().await?;
-                    let _ = ctx.update_item(Dunst::item(&ctx.config.theme, paused)).await;
+                    let _ = ctx.update_item(Dunst::item(&ctx.theme(), paused)).await;
                 },
                 Some(_) = ctx.wait_for_event(None) => {
                     let paused = dunst_proxy.paused().await?;
-                    let _ = ctx.update_item(Dunst::item(&ctx.config.theme, paused)).await;
+                    let _ = ctx.update_item(Dunst::item(&ctx.theme(), paused)).await;
                 }
             }
         }
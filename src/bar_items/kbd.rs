@@ -2,22 +2,26 @@ use std::error::Error;
 use std::time::Duration;
 
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use strum::{EnumIter, IntoEnumIterator};
 use tokio::fs;
 
-use crate::context::{BarEvent, BarItem, Context, StopAction};
+use crate::config::schema::DurationSchema;
+use crate::context::{BarItem, Context, StopAction};
 use crate::i3::{I3Item, I3Markup};
 use crate::theme::Theme;
+use crate::util::evdev_led_watch;
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Kbd {
     show: Option<Vec<Keys>>,
     #[serde(default, with = "crate::human_time::option")]
+    #[schemars(with = "Option<DurationSchema>")]
     interval: Option<Duration>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, EnumIter, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, EnumIter, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Keys {
     CapsLock,
@@ -97,8 +101,21 @@ impl BarItem for Kbd {
     async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
         let keys = self.show.clone().unwrap_or_else(|| Keys::iter().collect());
 
-        'outer: loop {
-            let text = futures::future::join_all(keys.iter().map(|k| k.format(&ctx.config.theme)))
+        // if input devices can't be read, then this only refreshes on signals or the interval
+        let mut leds = match evdev_led_watch().await {
+            Ok(rx) => Some(rx),
+            Err(e) => {
+                log::warn!(
+                    "failed to watch for LED changes, only refreshing on signals: {}",
+                    e
+                );
+                None
+            }
+        };
+
+        loop {
+            let theme = ctx.theme();
+            let text = futures::future::join_all(keys.iter().map(|k| k.format(&theme)))
                 .await
                 .into_iter()
                 .collect::<Result<Vec<_>, _>>()?
@@ -107,11 +124,10 @@ impl BarItem for Kbd {
             let item = I3Item::new(text).markup(I3Markup::Pango);
             ctx.update_item(item).await?;
 
-            // wait for a signal and then refresh
-            loop {
-                if let Some(BarEvent::Signal) = ctx.wait_for_event(self.interval).await {
-                    continue 'outer;
-                }
+            tokio::select! {
+                Some(()) = async { leds.as_mut()?.recv().await } => {}
+                // a signal, a click or the interval elapsing
+                _ = ctx.wait_for_event(self.interval) => {}
             }
         }
     }
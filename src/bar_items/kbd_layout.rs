@@ -0,0 +1,233 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use tokio::sync::mpsc::Receiver;
+
+use crate::context::{BarEvent, BarItem, Context, StopAction};
+use crate::i3::wm::{is_sway, I3Event, I3EventType, I3Input, I3Ipc};
+use crate::i3::{I3Button, I3Item, I3Markup};
+use crate::util::Placeholders;
+#[cfg(feature = "xkb")]
+use crate::util::Xkb;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KbdLayout {
+    /// Only used on sway: the identifier of the keyboard to show (see `swaymsg -t get_inputs`), if
+    /// not set then the first keyboard with layouts is used and layouts are changed for all keyboards.
+    identifier: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Layouts {
+    names: Vec<String>,
+    active: usize,
+}
+
+impl Layouts {
+    /// Find the layouts of the keyboard from sway's inputs.
+    fn from_inputs(inputs: Vec<I3Input>, identifier: Option<&str>) -> Option<Layouts> {
+        inputs
+            .into_iter()
+            .filter(|input| input.kind == "keyboard" && !input.xkb_layout_names.is_empty())
+            .find(|input| identifier.map_or(true, |id| input.identifier == id))
+            .map(|input| Layouts {
+                active: input.xkb_active_layout_index.unwrap_or_default(),
+                names: input.xkb_layout_names,
+            })
+    }
+
+    fn active_name(&self) -> &str {
+        self.names
+            .get(self.active)
+            .map(|name| name.as_str())
+            .unwrap_or_default()
+    }
+
+    fn cycle(&self, forwards: bool) -> usize {
+        let len = self.names.len();
+        if forwards {
+            (self.active + 1) % len
+        } else {
+            (self.active + len - 1) % len
+        }
+    }
+}
+
+/// i3 doesn't manage keyboard layouts, so on X11 they're read from the X server itself.
+enum Backend {
+    #[cfg(feature = "xkb")]
+    Xkb(Xkb),
+    Sway {
+        ipc: I3Ipc,
+        events: Receiver<I3Event>,
+    },
+}
+
+impl Backend {
+    async fn connect() -> Result<Backend, Box<dyn Error>> {
+        if is_sway() {
+            let events = I3Ipc::connect()
+                .await?
+                .subscribe(&[I3EventType::Input])
+                .await?;
+            let ipc = I3Ipc::connect().await?;
+            Ok(Backend::Sway { ipc, events })
+        } else {
+            Self::connect_xkb()
+        }
+    }
+
+    #[cfg(feature = "xkb")]
+    fn connect_xkb() -> Result<Backend, Box<dyn Error>> {
+        Ok(Backend::Xkb(Xkb::connect()?))
+    }
+
+    #[cfg(not(feature = "xkb"))]
+    fn connect_xkb() -> Result<Backend, Box<dyn Error>> {
+        bail!("keyboard layouts on X11 need istat to be built with the `xkb` feature")
+    }
+
+    async fn layouts(
+        &mut self,
+        identifier: Option<&str>,
+    ) -> Result<Option<Layouts>, Box<dyn Error>> {
+        match self {
+            #[cfg(feature = "xkb")]
+            Backend::Xkb(xkb) => {
+                let names = xkb.group_names()?;
+                if names.is_empty() {
+                    return Ok(None);
+                }
+
+                Ok(Some(Layouts {
+                    names,
+                    active: xkb.group()?,
+                }))
+            }
+            Backend::Sway { ipc, .. } => {
+                Ok(Layouts::from_inputs(ipc.get_inputs().await?, identifier))
+            }
+        }
+    }
+
+    async fn set_layout(
+        &mut self,
+        identifier: Option<&str>,
+        idx: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            #[cfg(feature = "xkb")]
+            Backend::Xkb(xkb) => xkb.lock_group(idx),
+            Backend::Sway { ipc, .. } => {
+                let target = match identifier {
+                    Some(id) => format!("\"{}\"", id),
+                    None => "type:keyboard".into(),
+                };
+                ipc.run_command(&format!("input {} xkb_switch_layout {}", target, idx))
+                    .await
+            }
+        }
+    }
+
+    async fn wait_for_change(&mut self) -> Result<(), Box<dyn Error>> {
+        match self {
+            #[cfg(feature = "xkb")]
+            Backend::Xkb(xkb) => xkb.wait_for_change().await,
+            Backend::Sway { events, .. } => loop {
+                match events.recv().await {
+                    Some(I3Event::Input(event)) if event.input.kind == "keyboard" => break Ok(()),
+                    Some(_) => continue,
+                    None => bail!("window manager event stream ended"),
+                }
+            },
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl BarItem for KbdLayout {
+    async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let identifier = self.identifier.as_deref();
+        let mut backend = Backend::connect().await?;
+
+        loop {
+            let layouts = backend.layouts(identifier).await?;
+            match &layouts {
+                Some(layouts) => {
+                    let name = layouts.active_name();
+                    let item = I3Item::new(format!("󰌌 {}", name))
+                        .short_text(name)
+                        .markup(I3Markup::Pango);
+                    let placeholders = Placeholders::new()
+                        .text("name", name)
+                        .text("idx", layouts.active);
+                    ctx.update_item_with(item, placeholders).await?;
+                }
+                None => ctx.update_item(I3Item::empty()).await?,
+            }
+
+            let event = tokio::select! {
+                result = backend.wait_for_change() => {
+                    result?;
+                    continue;
+                }
+                Some(event) = ctx.wait_for_event(None) => event,
+            };
+
+            // clicking cycles through the layouts
+            if let (Some(layouts), BarEvent::Click(click)) = (&layouts, event) {
+                let idx = match click.button {
+                    I3Button::Left | I3Button::ScrollUp => layouts.cycle(true),
+                    I3Button::Right | I3Button::ScrollDown => layouts.cycle(false),
+                    _ => continue,
+                };
+
+                backend.set_layout(identifier, idx).await?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(identifier: &str, kind: &str, names: &[&str], active: usize) -> I3Input {
+        I3Input {
+            identifier: identifier.into(),
+            kind: kind.into(),
+            xkb_layout_names: names.iter().map(|s| s.to_string()).collect(),
+            xkb_active_layout_index: Some(active),
+        }
+    }
+
+    #[test]
+    fn layouts_from_inputs() {
+        let inputs = || {
+            vec![
+                input("1:1:mouse", "pointer", &[], 0),
+                input("1:2:power", "keyboard", &[], 0),
+                input("1:3:keyboard", "keyboard", &["English (US)", "German"], 1),
+                input("1:4:other", "keyboard", &["French"], 0),
+            ]
+        };
+
+        let layouts = Layouts::from_inputs(inputs(), None).unwrap();
+        assert_eq!(layouts.active_name(), "German");
+        assert_eq!(layouts.cycle(true), 0);
+        assert_eq!(layouts.cycle(false), 0);
+
+        let layouts = Layouts::from_inputs(inputs(), Some("1:4:other")).unwrap();
+        assert_eq!(
+            layouts,
+            Layouts {
+                names: vec!["French".into()],
+                active: 0
+            }
+        );
+
+        assert_eq!(Layouts::from_inputs(inputs(), Some("none")), None);
+    }
+}
@@ -2,16 +2,19 @@ use std::error::Error;
 use std::time::Duration;
 
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use tokio::process::Command;
 
+use crate::config::schema::DurationSchema;
 use crate::context::{BarItem, Context, StopAction};
 use crate::i3::{I3Item, I3Markup};
 use crate::theme::Theme;
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Krb {
     #[serde(default, with = "crate::human_time::option")]
+    #[schemars(with = "Option<DurationSchema>")]
     interval: Option<Duration>,
 }
 
@@ -36,7 +39,7 @@ impl Krb {
 impl BarItem for Krb {
     async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
         loop {
-            ctx.update_item(self.item(&ctx.config.theme).await?).await?;
+            ctx.update_item(self.item(&ctx.theme()).await?).await?;
             ctx.wait_for_event(self.interval).await;
         }
     }
@@ -3,18 +3,19 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use bytesize::ByteSize;
-use hex_color::HexColor;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use strum::EnumIter;
 use sysinfo::SystemExt;
 
+use crate::config::schema::DurationSchema;
 use crate::context::{BarEvent, BarItem, Context, StopAction};
 use crate::i3::{I3Button, I3Item, I3Markup};
-use crate::theme::Theme;
+use crate::theme::ThemeColor;
 use crate::util::format::{float, FloatFormat};
-use crate::util::EnumCycle;
+use crate::util::{EnumCycle, Placeholders, Threshold, Thresholds};
 
-#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, EnumIter)]
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, EnumIter, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum MemDisplay {
     #[default]
@@ -22,24 +23,28 @@ pub enum MemDisplay {
     Percentage,
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Mem {
     #[serde(with = "crate::human_time")]
+    #[schemars(with = "DurationSchema")]
     interval: Duration,
     #[serde(flatten)]
     float_fmt: FloatFormat,
     #[serde(default)]
     display: MemDisplay,
+    /// Thresholds for the percentage of used memory.
+    #[serde(default = "Mem::default_thresholds")]
+    thresholds: Thresholds,
 }
 
 impl Mem {
-    fn get_color(theme: &Theme, used_pct: f64) -> Option<HexColor> {
-        match used_pct as u64 {
-            80..=100 => Some(theme.red),
-            60..=79 => Some(theme.orange),
-            40..=59 => Some(theme.yellow),
-            _ => None,
-        }
+    fn default_thresholds() -> Thresholds {
+        Thresholds::new(vec![
+            Threshold::new(40.0, Some(ThemeColor::Yellow)),
+            Threshold::new(60.0, Some(ThemeColor::Orange)),
+            Threshold::new(80.0, Some(ThemeColor::Red)),
+        ])
+        .unwrap()
     }
 }
 
@@ -63,12 +68,16 @@ impl BarItem for Mem {
                 MemDisplay::Percentage => format!("{}%", float(used_pct, &self.float_fmt)),
             };
 
-            let mut item = I3Item::new(format!(" {}", s)).markup(I3Markup::Pango);
-            if let Some(fg) = Self::get_color(&ctx.config.theme, used_pct) {
-                item = item.color(fg);
-            }
+            let item = I3Item::new(format!(" {}", s)).markup(I3Markup::Pango);
+            let item = self.thresholds.apply(&ctx.theme(), &used_pct, item);
+
+            let placeholders = Placeholders::new()
+                .float("used_pct", used_pct, &self.float_fmt)
+                .text("available", ByteSize(available).to_string_as(false))
+                .text("used", ByteSize(total - available).to_string_as(false))
+                .text("total", ByteSize(total).to_string_as(false));
 
-            ctx.update_item(item).await?;
+            ctx.update_item_with(item, placeholders).await?;
             ctx.delay_with_event_handler(self.interval, |ev| {
                 if let BarEvent::Click(c) = ev {
                     if let I3Button::Left = c.button {
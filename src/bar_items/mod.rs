@@ -1,3 +1,4 @@
 use_and_export!(
-    battery, cpu, disk, dunst, kbd, krb, mem, net_usage, nic, pulse, script, sensors, time
+    backlight, battery, bluetooth, cpu, disk, dunst, kbd, kbd_layout, krb, mem, mode, mpris,
+    net_usage, nic, pulse, scratchpad, script, sensors, time, window
 );
@@ -0,0 +1,68 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::context::{BarItem, Context, StopAction};
+use crate::i3::wm::{I3Event, I3EventType, I3Ipc};
+use crate::i3::{I3Item, I3Markup};
+use crate::theme::Theme;
+use crate::util::Placeholders;
+
+/// The name of the mode which is active when no other mode is.
+const DEFAULT_MODE: &str = "default";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Mode {}
+
+impl Mode {
+    fn item(theme: &Theme, mode: &str, pango_markup: bool) -> I3Item {
+        if mode == DEFAULT_MODE {
+            return I3Item::empty();
+        }
+
+        let item = I3Item::new(mode)
+            .color(theme.bg)
+            .background_color(theme.purple);
+
+        if pango_markup {
+            item.markup(I3Markup::Pango)
+        } else {
+            item
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl BarItem for Mode {
+    async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let mut events = I3Ipc::connect()
+            .await?
+            .subscribe(&[I3EventType::Mode])
+            .await?;
+
+        // get initial state, after subscribing so no changes are missed
+        let mut mode = I3Ipc::connect().await?.get_binding_state().await?;
+        // only known from mode events, the initial mode is the default mode most of the time anyway
+        let mut pango_markup = false;
+
+        loop {
+            let item = Mode::item(&ctx.theme(), &mode, pango_markup);
+            ctx.update_item_with(item, Placeholders::new().text("mode", &mode))
+                .await?;
+
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(I3Event::Mode(event)) => {
+                        mode = event.change;
+                        pango_markup = event.pango_markup;
+                    }
+                    Some(_) => {}
+                    None => bail!("window manager event stream ended"),
+                },
+                Some(_) = ctx.wait_for_event(None) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use clap::Parser;
+use futures::StreamExt;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zbus::fdo::DBusProxy;
+use zbus::{Connection, MatchRule, MessageStream, MessageType};
+
+use crate::context::{BarEvent, BarItem, Context, CustomResponse, StopAction};
+use crate::dbus::mpris::{metadata_text, MediaPlayer2PlayerProxy, MPRIS_PREFIX};
+use crate::dbus::{dbus_connection, BusType};
+use crate::i3::{I3Button, I3Item, I3Markup};
+use crate::theme::Theme;
+use crate::util::{escape, Paginator, Placeholders};
+
+#[derive(Debug, Parser)]
+#[command(name = "mpris", no_binary_name = true)]
+enum MprisCommand {
+    Info,
+    List,
+    PlayPause,
+    Play,
+    Pause,
+    Stop,
+    Next,
+    Previous,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "detail")]
+pub enum MprisResponse {
+    Info(Value),
+    List(Value),
+    Success,
+    Failure(String),
+}
+
+/// The state of a single media player.
+#[derive(Debug)]
+struct Player {
+    /// The player's bus name, without the MPRIS prefix (e.g., `spotify`)
+    name: String,
+    status: String,
+    artist: Option<String>,
+    title: Option<String>,
+}
+
+impl Player {
+    async fn fetch(proxy: &MediaPlayer2PlayerProxy<'_>, name: &str) -> zbus::Result<Player> {
+        let metadata = proxy.metadata().await?;
+        Ok(Player {
+            name: name.trim_start_matches(MPRIS_PREFIX).into(),
+            status: proxy.playback_status().await?,
+            artist: metadata_text(&metadata, "xesam:artist"),
+            title: metadata_text(&metadata, "xesam:title"),
+        })
+    }
+
+    fn to_value(&self) -> Value {
+        json!({
+            "name": self.name,
+            "status": self.status,
+            "artist": self.artist,
+            "title": self.title,
+        })
+    }
+
+    fn format(&self, theme: &Theme) -> (I3Item, Placeholders) {
+        let (icon, fg) = match self.status.as_str() {
+            "Playing" => ("󰐊", None),
+            "Paused" => ("󰏤", Some(theme.dim)),
+            _ => ("󰓛", Some(theme.dim)),
+        };
+
+        let text = match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+            (None, Some(title)) => title.clone(),
+            (Some(artist), None) => artist.clone(),
+            (None, None) => self.name.clone(),
+        };
+
+        let placeholders = Placeholders::new()
+            .text("icon", icon)
+            .text("name", &self.name)
+            .text("status", self.status.to_lowercase())
+            .text("artist", self.artist.as_deref().unwrap_or_default())
+            .text("title", self.title.as_deref().unwrap_or_default());
+
+        let item = I3Item::new(format!("{}  {}", icon, escape(&text)))
+            .short_text(escape(self.title.as_deref().unwrap_or(&text)));
+        let item = match fg {
+            Some(fg) => item.color(fg),
+            None => item,
+        };
+
+        (item, placeholders)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Mpris {
+    /// Only show players with these names (e.g., `spotify` or `firefox`), if empty then all players
+    /// are shown. Names are matched against the start of the player's bus name, without the
+    /// `org.mpris.MediaPlayer2.` prefix.
+    #[serde(default)]
+    players: Vec<String>,
+}
+
+impl Mpris {
+    /// Find the bus names of all the running players.
+    async fn find_players(&self, dbus: &DBusProxy<'_>) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut names = dbus
+            .list_names()
+            .await?
+            .into_iter()
+            .map(|name| name.to_string())
+            .filter(|name| match name.strip_prefix(MPRIS_PREFIX) {
+                Some(name) => {
+                    self.players.is_empty() || self.players.iter().any(|p| name.starts_with(p))
+                }
+                None => false,
+            })
+            .collect::<Vec<_>>();
+
+        // keep the order stable, so the shown player doesn't change when other players appear
+        names.sort();
+        Ok(names)
+    }
+
+    async fn player_proxy<'a>(
+        connection: &'a Connection,
+        name: &str,
+    ) -> Result<MediaPlayer2PlayerProxy<'a>, Box<dyn Error>> {
+        Ok(MediaPlayer2PlayerProxy::builder(connection)
+            .destination(name.to_string())?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?)
+    }
+
+    async fn handle_custom_message(
+        connection: &Connection,
+        players: &[Player],
+        current: Option<&str>,
+        args: Vec<String>,
+    ) -> CustomResponse {
+        let cmd = match MprisCommand::try_parse_from(args) {
+            Ok(cmd) => cmd,
+            Err(e) => return CustomResponse::Help(e.render()),
+        };
+
+        let current_player = players.iter().find(|p| Some(p.name.as_str()) == current);
+        let resp = match cmd {
+            MprisCommand::Info => {
+                MprisResponse::Info(current_player.map_or(Value::Null, Player::to_value))
+            }
+            MprisCommand::List => {
+                MprisResponse::List(players.iter().map(Player::to_value).collect())
+            }
+            cmd => match current {
+                Some(name) => match Self::control(connection, name, cmd).await {
+                    Ok(()) => MprisResponse::Success,
+                    Err(e) => MprisResponse::Failure(e.to_string()),
+                },
+                None => MprisResponse::Failure("no players found".into()),
+            },
+        };
+
+        CustomResponse::Json(json!(resp))
+    }
+
+    async fn control(
+        connection: &Connection,
+        name: &str,
+        cmd: MprisCommand,
+    ) -> Result<(), Box<dyn Error>> {
+        let proxy = Self::player_proxy(connection, &format!("{}{}", MPRIS_PREFIX, name)).await?;
+        match cmd {
+            MprisCommand::PlayPause => proxy.play_pause().await?,
+            MprisCommand::Play => proxy.play().await?,
+            MprisCommand::Pause => proxy.pause().await?,
+            MprisCommand::Stop => proxy.stop().await?,
+            MprisCommand::Next => proxy.next().await?,
+            MprisCommand::Previous => proxy.previous().await?,
+            MprisCommand::Info | MprisCommand::List => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl BarItem for Mpris {
+    async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let connection = dbus_connection(BusType::Session).await?;
+        let dbus = DBusProxy::new(&connection).await?;
+
+        // players appearing or disappearing, filtered by the bus since other names change often
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender("org.freedesktop.DBus")?
+            .interface("org.freedesktop.DBus")?
+            .member("NameOwnerChanged")?
+            .arg0namespace(MPRIS_PREFIX.trim_end_matches('.'))?
+            .build();
+        let mut name_changes = MessageStream::for_match_rule(rule, &connection, None).await?;
+        // changes to the state of any player
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface("org.freedesktop.DBus.Properties")?
+            .member("PropertiesChanged")?
+            .path("/org/mpris/MediaPlayer2")?
+            .build();
+        let mut property_changes = MessageStream::for_match_rule(rule, &connection, None).await?;
+
+        let mut p = Paginator::new();
+        loop {
+            let mut players = vec![];
+            for name in self.find_players(&dbus).await? {
+                let player = match Self::player_proxy(&connection, &name).await {
+                    Ok(proxy) => Player::fetch(&proxy, &name).await.map_err(Into::into),
+                    Err(e) => Err(e),
+                };
+
+                match player {
+                    Ok(player) => players.push(player),
+                    // the player may have exited since it was found
+                    Err(e) => log::warn!("failed to read mpris player {}: {}", name, e),
+                }
+            }
+
+            let current = if players.is_empty() {
+                ctx.update_item(I3Item::empty()).await?;
+                None
+            } else {
+                p.set_len(players.len());
+                let theme = &ctx.theme();
+                let player = &players[p.idx()];
+                let (item, placeholders) = player.format(theme);
+                let page = p.format(theme);
+                let full_text = format!("{}{}", item.get_full_text(), page);
+                let item = item.full_text(full_text).markup(I3Markup::Pango);
+                ctx.update_item_with(item, placeholders.markup("page", page))
+                    .await?;
+                Some(player.name.clone())
+            };
+
+            tokio::select! {
+                Some(event) = ctx.raw_event_rx().recv() => {
+                    let cmd = match event {
+                        BarEvent::Custom { payload, responder } => {
+                            let resp = Self::handle_custom_message(
+                                &connection,
+                                &players,
+                                current.as_deref(),
+                                payload,
+                            )
+                            .await;
+                            let _ = responder.send(resp);
+                            None
+                        }
+                        BarEvent::Click(click) => match click.button {
+                            I3Button::Left => Some(MprisCommand::PlayPause),
+                            I3Button::Middle => Some(MprisCommand::Previous),
+                            I3Button::Right => Some(MprisCommand::Next),
+                            // scrolling cycles through players
+                            I3Button::ScrollUp | I3Button::ScrollDown => {
+                                p.update(&BarEvent::Click(click));
+                                None
+                            }
+                        },
+                        event => {
+                            p.update(&event);
+                            None
+                        }
+                    };
+
+                    if let (Some(cmd), Some(name)) = (cmd, &current) {
+                        if let Err(e) = Self::control(&connection, name, cmd).await {
+                            log::warn!("failed to control mpris player {}: {}", name, e);
+                        }
+                    }
+                }
+                Some(_) = name_changes.next() => {}
+                Some(_) = property_changes.next() => {}
+            }
+        }
+    }
+}
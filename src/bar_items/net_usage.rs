@@ -4,17 +4,19 @@ use std::time::Duration;
 use async_trait::async_trait;
 use bytesize::ByteSize;
 use hex_color::HexColor;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use strum::EnumIter;
 use sysinfo::{NetworkExt, NetworksExt, SystemExt};
 use tokio::time::Instant;
 
+use crate::config::schema::{ByteSizeSchema, DurationSchema};
 use crate::context::{BarEvent, BarItem, Context, StopAction};
 use crate::i3::{I3Button, I3Item, I3Markup};
-use crate::theme::Theme;
-use crate::util::EnumCycle;
+use crate::theme::{Theme, ThemeColor};
+use crate::util::{EnumCycle, FloatFormat, Placeholders, Threshold, Thresholds};
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, EnumIter)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, EnumIter, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 enum UsageDisplay {
     // as bits: 1 Kbit == 8000 bits == 1000 B
@@ -26,55 +28,89 @@ enum UsageDisplay {
     Bibytes,
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct NetUsage {
     #[serde(with = "crate::human_time")]
+    #[schemars(with = "DurationSchema")]
     interval: Duration,
+    #[schemars(with = "Option<ByteSizeSchema>")]
     minimum: Option<ByteSize>,
-    #[serde(default)]
-    thresholds: Vec<ByteSize>,
+    #[serde(default, deserialize_with = "deserialize_thresholds")]
+    #[schemars(with = "ThresholdsCompat")]
+    thresholds: Thresholds<ByteSize>,
     #[serde(default)]
     ignored_interfaces: Vec<String>,
     #[serde(default)]
     display: UsageDisplay,
     /// Currently only surfaced for testing.
     #[serde(default)]
+    #[schemars(skip)]
     _always_assume_interval: bool,
 }
 
 impl NetUsage {
     fn get_color(&self, theme: &Theme, bytes: u64) -> Option<HexColor> {
-        if self.thresholds.len() == 0 {
-            return None;
-        }
+        self.thresholds.get_color(theme, &ByteSize(bytes))
+    }
 
-        let end = self
-            .thresholds
-            .first()
-            .map(|b| b.as_u64())
-            .unwrap_or(u64::MAX);
+    fn is_urgent(&self, bytes: u64) -> bool {
+        self.thresholds
+            .find(&ByteSize(bytes))
+            .map_or(false, |t| t.urgent)
+    }
+}
 
-        if (0..=end).contains(&bytes) {
-            return Some(theme.dim);
-        }
+/// Thresholds used to be a list of up to 5 sizes, which mapped to a fixed set of colours. That format
+/// is still accepted, and is converted into the equivalent `Thresholds`.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum ThresholdsCompat {
+    Legacy(#[schemars(with = "Vec<ByteSizeSchema>")] Vec<ByteSize>),
+    Thresholds(#[schemars(with = "Thresholds<ByteSizeSchema>")] Thresholds<ByteSize>),
+}
 
-        // NOTE: since we have 5 thresholds, and windows of 2, there will only be 4 windows
-        // so we only need to map it to 4 colours here
-        let threshold_colors = &[
-            None,
-            Some(theme.yellow),
-            Some(theme.orange),
-            Some(theme.red),
-        ];
-        for (idx, w) in self.thresholds.windows(2).enumerate() {
-            if (w[0].as_u64()..w[1].as_u64()).contains(&bytes) {
-                return threshold_colors[idx];
-            }
+fn deserialize_thresholds<'de, D>(d: D) -> Result<Thresholds<ByteSize>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match <ThresholdsCompat as serde::Deserialize>::deserialize(d)? {
+        ThresholdsCompat::Legacy(sizes) => {
+            legacy_thresholds(&sizes).map_err(serde::de::Error::custom)
         }
+        ThresholdsCompat::Thresholds(thresholds) => Ok(thresholds),
+    }
+}
 
-        // it was above any of the thresholds listed
-        Some(theme.purple)
+/// Up to and including the first size is `dim`, then each following window is the default colour,
+/// `yellow`, `orange` and `red`, and anything above the last size is `purple`.
+fn legacy_thresholds(sizes: &[ByteSize]) -> Result<Thresholds<ByteSize>, String> {
+    let (first, last) = match (sizes.first(), sizes.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(Thresholds::default()),
+    };
+
+    let window_colors = [
+        None,
+        Some(ThemeColor::Yellow),
+        Some(ThemeColor::Orange),
+        Some(ThemeColor::Red),
+    ];
+
+    let mut thresholds = vec![
+        Threshold::new(ByteSize(0), Some(ThemeColor::Dim)),
+        Threshold::new(ByteSize(first.as_u64() + 1), window_colors[0]),
+    ];
+    for (idx, size) in sizes.iter().enumerate().take(sizes.len() - 1).skip(1) {
+        let color = window_colors.get(idx).copied().unwrap_or(window_colors[3]);
+        thresholds.push(Threshold::new(*size, color));
     }
+    if sizes.len() > 1 {
+        thresholds.push(Threshold::new(*last, Some(ThemeColor::Purple)));
+    } else {
+        thresholds[1].color = Some(ThemeColor::Purple);
+    }
+
+    Thresholds::new(thresholds).map_err(|e| e.to_string())
 }
 
 fn format_bytes(bytes: u64, si: bool, as_bits: bool) -> String {
@@ -144,17 +180,30 @@ impl BarItem for NetUsage {
                 }
             };
 
-            ctx.update_item(
-                I3Item::new(format!(
-                    "<span{}>{}↓</span> <span{}>{}↑</span>",
-                    fg(down, &ctx.config.theme),
-                    text(down, *display.current()),
-                    fg(up, &ctx.config.theme),
-                    text(up, *display.current())
-                ))
-                .markup(I3Markup::Pango),
-            )
-            .await?;
+            let (down_text, up_text) =
+                (text(down, *display.current()), text(up, *display.current()));
+            let item = I3Item::new(format!(
+                "<span{}>{}↓</span> <span{}>{}↑</span>",
+                fg(down, &ctx.theme()),
+                down_text,
+                fg(up, &ctx.theme()),
+                up_text
+            ))
+            .markup(I3Markup::Pango);
+            let item = if self.is_urgent(down) || self.is_urgent(up) {
+                item.urgent(true)
+            } else {
+                item
+            };
+
+            let bytes_fmt = FloatFormat::default();
+            let placeholders = Placeholders::new()
+                .text("down", down_text.trim())
+                .text("up", up_text.trim())
+                .float("down_bytes", down as f64, &bytes_fmt)
+                .float("up_bytes", up as f64, &bytes_fmt)
+                .float("minimum", min as f64, &bytes_fmt);
+            ctx.update_item_with(item, placeholders).await?;
 
             // swap between bits and bytes on click
             if let Some(event) = ctx.wait_for_event(Some(self.interval)).await {
@@ -167,3 +216,37 @@ impl BarItem for NetUsage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_thresholds_conversion() {
+        let theme = Theme::default();
+        let sizes =
+            ["1kiB", "1MiB", "10MiB", "25MiB", "100MiB"].map(|s| s.parse::<ByteSize>().unwrap());
+        let thresholds = legacy_thresholds(&sizes).unwrap();
+        let color = |bytes: u64| thresholds.get_color(&theme, &ByteSize(bytes));
+
+        assert_eq!(color(0), Some(theme.dim));
+        assert_eq!(color(bytesize::KIB), Some(theme.dim));
+        assert_eq!(color(bytesize::KIB + 1), None);
+        assert_eq!(color(bytesize::MIB), Some(theme.yellow));
+        assert_eq!(color(10 * bytesize::MIB), Some(theme.orange));
+        assert_eq!(color(25 * bytesize::MIB), Some(theme.red));
+        assert_eq!(color(100 * bytesize::MIB), Some(theme.purple));
+
+        let thresholds = legacy_thresholds(&sizes[..1]).unwrap();
+        assert_eq!(
+            thresholds.get_color(&theme, &ByteSize(bytesize::KIB)),
+            Some(theme.dim)
+        );
+        assert_eq!(
+            thresholds.get_color(&theme, &ByteSize(bytesize::MIB)),
+            Some(theme.purple)
+        );
+
+        assert!(legacy_thresholds(&[]).unwrap().is_empty());
+    }
+}
@@ -19,6 +19,11 @@ impl InterfaceFilter {
         }
     }
 
+    /// The interface name to match, this is empty if all interfaces are matched.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn matches(&self, interface: &Interface) -> bool {
         let name_match = if self.name.is_empty() {
             true
@@ -1,22 +1,25 @@
 mod filter;
 
 use std::error::Error;
+use std::path::Path;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::StreamExt;
 use hex_color::HexColor;
 use iwlib::WirelessInfo;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 
-use self::filter::InterfaceFilter;
+pub use self::filter::InterfaceFilter;
+use crate::config::schema::DurationSchema;
 use crate::context::{BarItem, Context, StopAction};
 use crate::dbus::network_manager::NetworkManagerProxy;
 use crate::dbus::{dbus_connection, BusType};
 use crate::i3::{I3Item, I3Markup};
 use crate::theme::Theme;
 use crate::util::net::Interface;
-use crate::util::Paginator;
+use crate::util::{Paginator, Placeholders};
 
 impl Interface {
     fn format_wireless(&self, i: WirelessInfo, theme: &Theme) -> (String, Option<HexColor>) {
@@ -38,9 +41,17 @@ impl Interface {
         (format!("({})", self.addr), Some(theme.green))
     }
 
-    fn format(&mut self, theme: &Theme) -> (String, String) {
+    fn format(&mut self, theme: &Theme) -> (String, String, Placeholders) {
+        let mut placeholders = Placeholders::new()
+            .text("interface", &self.name)
+            .text("addr", &self.addr);
         let (addr, fg) = match self.get_wireless_info() {
-            Some(info) => self.format_wireless(info, theme),
+            Some(info) => {
+                placeholders = placeholders
+                    .text("essid", &info.wi_essid)
+                    .text("quality", info.wi_quality);
+                self.format_wireless(info, theme)
+            }
             None => self.format_normal(theme),
         };
 
@@ -50,13 +61,15 @@ impl Interface {
         (
             format!(r#"<span{}>{}{}</span>"#, fg, self.name, addr),
             format!(r#"<span{}>{}</span>"#, fg, self.name),
+            placeholders,
         )
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Nic {
     #[serde(default, with = "crate::human_time::option")]
+    #[schemars(with = "Option<DurationSchema>")]
     interval: Option<Duration>,
     /// This type is in the format of `interface[:type]`, where `interface` is the interface name, and
     /// `type` is an optional part which is either `ipv4` or `ipv6`.
@@ -69,6 +82,18 @@ pub struct Nic {
     filter: Vec<InterfaceFilter>,
 }
 
+impl Nic {
+    /// Ensure the interfaces named in the filters exist.
+    pub fn check(&self) -> Vec<String> {
+        let net_dir = Path::new("/sys/class/net");
+        self.filter
+            .iter()
+            .filter(|f| !f.name().is_empty() && !net_dir.join(f.name()).exists())
+            .map(|f| format!("no interface found for filter: {}", f.to_string()))
+            .collect()
+    }
+}
+
 #[async_trait(?Send)]
 impl BarItem for Nic {
     async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
@@ -91,7 +116,7 @@ impl BarItem for Nic {
 
             // no networks active
             if interfaces.is_empty() {
-                ctx.update_item(I3Item::new("inactive").color(ctx.config.theme.dim))
+                ctx.update_item(I3Item::new("inactive").color(ctx.theme().dim))
                     .await?;
 
                 tokio::select! {
@@ -102,12 +127,14 @@ impl BarItem for Nic {
 
             p.set_len(interfaces.len());
 
-            let theme = &ctx.config.theme;
-            let (full, short) = interfaces[p.idx()].format(theme);
-            let full = format!(r#"{}{}"#, full, p.format(theme));
+            let theme = &ctx.theme();
+            let (full, short, placeholders) = interfaces[p.idx()].format(theme);
+            let page = p.format(theme);
+            let full = format!(r#"{}{}"#, full, page);
 
             let item = I3Item::new(full).short_text(short).markup(I3Markup::Pango);
-            ctx.update_item(item).await?;
+            ctx.update_item_with(item, placeholders.markup("page", page))
+                .await?;
 
             tokio::select! {
                 // update on network manager changes
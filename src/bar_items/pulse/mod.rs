@@ -24,6 +24,7 @@ use libpulse_binding::proplist::Proplist;
 use libpulse_binding::volume::{ChannelVolumes, Volume};
 use libpulse_tokio::TokioMain;
 use num_traits::ToPrimitive;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use tokio::sync::mpsc::{self, UnboundedSender};
 
@@ -180,7 +181,7 @@ enum Command {
     },
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 enum NotificationSetting {
     /// No notifications are sent (the default)
@@ -205,7 +206,7 @@ impl NotificationSetting {
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Pulse {
     /// How much to increment when increasing/decreasing the volume; measured in percent
     #[serde(default = "Pulse::default_increment")]
@@ -732,7 +733,7 @@ impl BarItem for Pulse {
                 // whenever we want to refresh our item, an event it send on this channel
                 Some(cmd) = rx.recv() => match cmd {
                     Command::UpdateItem(cb) => {
-                        ctx.update_item(cb(&ctx.config.theme)).await?;
+                        ctx.update_item(cb(&ctx.theme())).await?;
                     }
                     Command::NotifyVolume { name, volume, mute } => {
                         if self.notify.should_notify(NotificationSetting::VolumeMute) {
@@ -0,0 +1,62 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::context::{BarEvent, BarItem, Context, StopAction};
+use crate::i3::wm::{I3EventType, I3Ipc, SCRATCHPAD_WORKSPACE};
+use crate::i3::{I3Button, I3Item, I3Markup};
+use crate::util::Placeholders;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Scratchpad {}
+
+impl Scratchpad {
+    fn item(count: usize) -> I3Item {
+        if count == 0 {
+            return I3Item::empty();
+        }
+
+        I3Item::new(format!("󰖲 {}", count)).markup(I3Markup::Pango)
+    }
+}
+
+#[async_trait(?Send)]
+impl BarItem for Scratchpad {
+    async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let mut events = I3Ipc::connect()
+            .await?
+            .subscribe(&[I3EventType::Window])
+            .await?;
+        let mut ipc = I3Ipc::connect().await?;
+
+        loop {
+            let tree = ipc.get_tree().await?;
+            let count = tree
+                .find(&|node| node.name.as_deref() == Some(SCRATCHPAD_WORKSPACE))
+                .map_or(0, |workspace| workspace.window_count());
+            ctx.update_item_with(
+                Scratchpad::item(count),
+                Placeholders::new().text("count", count),
+            )
+            .await?;
+
+            tokio::select! {
+                // windows are moved to and from the scratchpad with window events
+                event = events.recv() => if event.is_none() {
+                    bail!("window manager event stream ended");
+                },
+                Some(event) = ctx.wait_for_event(None) => match event {
+                    BarEvent::Click(click) if click.button == I3Button::Left => {
+                        // e.g.: the scratchpad is empty
+                        if let Err(e) = ipc.run_command("scratchpad show").await {
+                            log::warn!("failed to show scratchpad: {}", e);
+                        }
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+}
@@ -1,51 +1,320 @@
 use std::collections::HashMap;
 use std::error::Error;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
 use std::time::Duration;
 
 use async_trait::async_trait;
+use futures::try_join;
+use hex_color::HexColor;
+use humantime_serde::re::humantime::format_duration;
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::Pid;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command;
+use tokio::time::{timeout_at, Instant};
 
+use crate::config::schema::DurationSchema;
 use crate::context::{BarEvent, BarItem, Context, StopAction};
 use crate::i3::{I3Item, I3Markup};
+use crate::theme::Theme;
+use crate::util::which;
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 #[allow(dead_code)]
 pub enum ScriptFormat {
     #[default]
     Simple,
     Json,
+    /// The same as i3blocks: the first line is the full text, the second the short text and the
+    /// third the colour. Only the full text is required.
+    I3blocks,
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Script {
     pub command: String,
     #[serde(default)]
     pub output: ScriptFormat,
     #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<DurationSchema>")]
     interval: Option<Duration>,
     #[serde(default)]
     pub markup: I3Markup,
+    /// Spawn the command once and keep it running, rather than running it on each refresh.
+    /// Each line it writes to STDOUT updates the item, and click events are written to its STDIN.
+    #[serde(default)]
+    pub persistent: bool,
+    /// Kill the script (and any processes it started) if it runs for longer than this.
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<DurationSchema>")]
+    timeout: Option<Duration>,
 }
 
 impl Script {
-    // returns stdout
-    async fn run(&self, env: &HashMap<&str, String>) -> Result<String, Box<dyn Error>> {
-        let output = Command::new("sh")
+    /// The exit code a script uses to mark the item as urgent (the same as i3blocks).
+    const EXIT_URGENT: i32 = 33;
+    /// The exit code a script uses to hide the item.
+    const EXIT_HIDE: i32 = 34;
+
+    /// How long to wait before restarting a persistent script which has exited.
+    const MIN_BACKOFF: Duration = Duration::from_secs(1);
+    const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+    /// Shell keywords and builtins which don't exist as programs.
+    const SHELL_BUILTINS: &[&str] = &[
+        "!", ".", ":", "[[", "case", "cd", "eval", "exec", "exit", "export", "for", "if", "read",
+        "set", "source", "until", "while",
+    ];
+
+    /// Ensure the program the command runs can be found.
+    pub fn check(&self) -> Vec<String> {
+        let program = match self.command.split_whitespace().next() {
+            Some(program) => program,
+            None => return vec!["command is empty".into()],
+        };
+
+        // the command is run with `sh`, so skip anything that needs a shell to resolve it
+        if program.contains(['=', '$', '`', '"', '\'', '(', '{'])
+            || Self::SHELL_BUILTINS.contains(&program)
+        {
+            return vec![];
+        }
+
+        // the shell expands `~` to the user's home directory (other forms such as `~user` are skipped)
+        let path = match program.strip_prefix("~/") {
+            Some(rest) => match dirs::home_dir() {
+                Some(home) => home.join(rest),
+                None => return vec![],
+            },
+            None if program.starts_with('~') => return vec![],
+            None => PathBuf::from(program),
+        };
+
+        match which(path) {
+            Some(_) => vec![],
+            None => vec![format!("command not found: {}", program)],
+        }
+    }
+
+    // returns stdout and the exit code, or `None` if the script timed out
+    async fn run(
+        &self,
+        ctx: &mut Context,
+        env: &HashMap<&str, String>,
+    ) -> Result<Option<(String, Option<i32>)>, Box<dyn Error>> {
+        let mut child = Command::new("sh")
             .arg("-c")
             .arg(&self.command)
             .envs(env)
-            .output()
-            .await?;
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // run in its own process group, so everything it started can be killed if it times out
+            .process_group(0)
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let pid = child.id();
+        let (mut stdout, mut stderr) = match (child.stdout.take(), child.stderr.take()) {
+            (Some(stdout), Some(stderr)) => (stdout, stderr),
+            _ => bail!("failed to read script's output"),
+        };
 
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        // the output is read into these as it arrives, so it's kept even if the script times out
+        let (mut out, mut err) = (vec![], vec![]);
+        let output = async {
+            try_join!(
+                stdout.read_to_end(&mut out),
+                stderr.read_to_end(&mut err),
+                child.wait()
+            )
+        };
+        // either the exit status, or how long the script ran for before timing out
+        let status = match self.timeout {
+            None => Ok(output.await?.2),
+            Some(timeout) => match tokio::time::timeout(timeout, output).await {
+                Ok(output) => Ok(output?.2),
+                Err(_) => Err(timeout),
+            },
+        };
+
+        if status.is_err() {
+            if let Some(pid) = pid {
+                if let Err(e) = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL) {
+                    log::error!("failed to kill script: {}", e);
+                }
+            }
+        }
+
+        for line in String::from_utf8_lossy(&err).lines() {
+            log::warn!("script stderr: {}", line);
+            ctx.log(line);
+        }
+
+        match status {
+            Ok(status) => Ok(Some((
+                String::from_utf8_lossy(&out).trim().to_string(),
+                status.code(),
+            ))),
+            Err(timeout) => {
+                let message = format!("timed out after {}", format_duration(timeout));
+                log::warn!("script {}", message);
+                ctx.log(message);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Apply the script's exit code to the item, in the same way as i3blocks: 0 is a success, 33
+    /// marks the item as urgent and anything else is an error. 34 hides the item.
+    fn apply_exit_code(item: I3Item, code: Option<i32>, theme: &Theme) -> I3Item {
+        match code {
+            Some(0) => item,
+            Some(Self::EXIT_URGENT) => item.urgent(true),
+            Some(Self::EXIT_HIDE) => item.hidden(true),
+            // this includes scripts which were killed by a signal
+            _ => item.color(theme.bg).background_color(theme.red),
+        }
+    }
+
+    /// Create an item from the script's output.
+    fn to_item(&self, ctx: &Context, stdout: &str) -> I3Item {
+        let item = match self.output {
+            ScriptFormat::Simple => I3Item::new(stdout),
+            ScriptFormat::Json => match serde_json::from_str(stdout) {
+                Ok(item) => item,
+                Err(e) => {
+                    log::error!("failed to parse script json output: {}", e);
+                    I3Item::new("ERROR").background_color(ctx.theme().red)
+                }
+            },
+            ScriptFormat::I3blocks => Self::parse_i3blocks(stdout),
+        };
+
+        item.markup(self.markup)
+    }
+
+    fn parse_i3blocks(stdout: &str) -> I3Item {
+        let mut lines = stdout.lines();
+        let mut item = I3Item::new(lines.next().unwrap_or_default());
+        if let Some(short_text) = lines.next().filter(|line| !line.is_empty()) {
+            item = item.short_text(short_text);
+        }
+
+        if let Some(color) = lines.next().filter(|line| !line.is_empty()) {
+            match HexColor::parse(color.trim()) {
+                Ok(color) => item = item.color(color),
+                Err(e) => log::warn!("failed to parse script colour {}: {}", color, e),
+            }
+        }
+
+        item
+    }
+
+    /// Run the command until it exits, updating the item with each line it writes to STDOUT and
+    /// writing click events to its STDIN as JSON lines.
+    async fn run_persistent(&self, ctx: &mut Context) -> Result<ExitStatus, Box<dyn Error>> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // run in its own process group, so everything it started is killed along with it
+            .process_group(0)
+            .kill_on_drop(true)
+            .spawn()?;
+
+        // this is dropped when the script exits, or when the item is removed or reloaded
+        let _group = ProcessGroup(child.id());
+        let mut stdin = child.stdin.take();
+        let (mut lines, mut errors) = match (child.stdout.take(), child.stderr.take()) {
+            (Some(stdout), Some(stderr)) => (
+                BufReader::new(stdout).lines(),
+                BufReader::new(stderr).lines(),
+            ),
+            _ => bail!("failed to read script's output"),
+        };
+
+        loop {
+            tokio::select! {
+                line = lines.next_line() => match line? {
+                    Some(line) => ctx.update_item(self.to_item(ctx, line.trim())).await?,
+                    None => break,
+                },
+                Ok(Some(line)) = errors.next_line() => {
+                    log::warn!("script stderr: {}", line);
+                    ctx.log(line);
+                }
+                Some(BarEvent::Click(click)) = ctx.raw_event_rx().recv() => {
+                    let mut line = serde_json::to_string(&click)?;
+                    line.push('\n');
+                    if let Some(writer) = &mut stdin {
+                        // the script may have closed its STDIN if it doesn't handle clicks
+                        if let Err(e) = writer.write_all(line.as_bytes()).await {
+                            log::warn!("failed to send click to script: {}", e);
+                            stdin = None;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(child.wait().await?)
+    }
+
+    /// Keep the command running, restarting it with an increasing delay each time it exits quickly.
+    async fn start_persistent(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let mut backoff = Self::MIN_BACKOFF;
+        loop {
+            let started = Instant::now();
+            match self.run_persistent(&mut ctx).await {
+                Ok(status) => log::warn!("persistent script exited with {}", status),
+                // e.g., the script couldn't be spawned or it wrote invalid UTF-8, so restart it
+                Err(e) => {
+                    let message = format!("persistent script failed: {}", e);
+                    log::error!("{}", message);
+                    ctx.log(message);
+                }
+            }
+
+            // the script ran for a while, so it's not stuck in a restart loop
+            if started.elapsed() > Self::MAX_BACKOFF {
+                backoff = Self::MIN_BACKOFF;
+            }
+
+            // clicks are ignored until the script is running again
+            let deadline = Instant::now() + backoff;
+            while let Ok(Some(_)) = timeout_at(deadline, ctx.raw_event_rx().recv()).await {}
+            backoff = (backoff * 2).min(Self::MAX_BACKOFF);
+        }
+    }
+}
+
+/// Kills a process group when dropped, since `kill_on_drop` only kills the script's shell and not
+/// any processes it started.
+struct ProcessGroup(Option<u32>);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(pid) = self.0 {
+            // the group may already be gone if the script exited and left nothing running
+            let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL);
+        }
     }
 }
 
 #[async_trait(?Send)]
 impl BarItem for Script {
     async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        if self.persistent {
+            return self.start_persistent(ctx).await;
+        }
+
         // update script environment on any click event
         let mut script_env = HashMap::new();
         let handle_event = |event: BarEvent, env: &mut HashMap<_, _>| match event {
@@ -54,47 +323,19 @@ impl BarItem for Script {
             }
             BarEvent::Click(c) => {
                 env.remove("I3_SIGNAL");
-                c.name.map(|name| {
-                    env.insert("I3_NAME", name.to_string());
-                });
-                env.insert(
-                    "I3_MODIFIERS",
-                    c.modifiers
-                        .iter()
-                        // SAFETY: if these types don't serialise then things would have gone wrong previously
-                        .map(|m| serde_json::to_string(m).unwrap())
-                        .collect::<Vec<_>>()
-                        .join(","),
-                );
-                // SAFETY: if these types don't serialise then things would have gone wrong previously
-                env.insert("I3_BUTTON", serde_json::to_string(&c.button).unwrap());
-                env.insert("I3_X", c.x.to_string());
-                env.insert("I3_Y", c.y.to_string());
-                env.insert("I3_RELATIVE_X", c.relative_x.to_string());
-                env.insert("I3_RELATIVE_Y", c.relative_y.to_string());
-                env.insert("I3_OUTPUT_X", c.output_x.to_string());
-                env.insert("I3_OUTPUT_Y", c.output_y.to_string());
-                env.insert("I3_WIDTH", c.width.to_string());
-                env.insert("I3_HEIGHT", c.height.to_string());
+                env.extend(c.to_env());
             }
             _ => {}
         };
 
         loop {
             // Initial run has no click environment variables
-            let stdout = self.run(&script_env).await?;
-            let mut item = match self.output {
-                ScriptFormat::Simple => I3Item::new(stdout),
-                ScriptFormat::Json => match serde_json::from_str(&stdout) {
-                    Ok(item) => item,
-                    Err(e) => {
-                        log::error!("failed to parse script json output: {}", e);
-                        I3Item::new("ERROR").background_color(ctx.config.theme.red)
-                    }
-                },
+            let item = match self.run(&mut ctx, &script_env).await? {
+                Some((stdout, code)) => {
+                    Self::apply_exit_code(self.to_item(&ctx, &stdout), code, &ctx.theme())
+                }
+                None => Self::apply_exit_code(I3Item::new("TIMEOUT"), None, &ctx.theme()),
             };
-            item = item.markup(self.markup);
-
             ctx.update_item(item).await?;
 
             match self.interval {
@@ -2,33 +2,57 @@ use std::error::Error;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use hex_color::HexColor;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
-use sysinfo::{ComponentExt, SystemExt};
-use tokio::time::sleep;
+use sysinfo::{ComponentExt, System, SystemExt};
 
+use crate::config::schema::DurationSchema;
 use crate::context::{BarItem, Context, StopAction};
 use crate::i3::{I3Item, I3Markup};
-use crate::theme::Theme;
+use crate::theme::ThemeColor;
 use crate::util::format::{float, FloatFormat};
+use crate::util::{Placeholders, Threshold, Thresholds};
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Sensors {
     #[serde(with = "crate::human_time")]
+    #[schemars(with = "DurationSchema")]
     interval: Duration,
     label: String,
     #[serde(flatten)]
     float_fmt: FloatFormat,
+    #[serde(default = "Sensors::default_thresholds")]
+    thresholds: Thresholds,
 }
 
 impl Sensors {
-    fn get_icon(theme: &Theme, temp: u32) -> (&'static str, Option<HexColor>) {
+    fn default_thresholds() -> Thresholds {
+        Thresholds::new(vec![
+            Threshold::new(60.0, Some(ThemeColor::Yellow)),
+            Threshold::new(80.0, Some(ThemeColor::Orange)),
+            Threshold::new(90.0, Some(ThemeColor::Red)),
+        ])
+        .unwrap()
+    }
+
+    /// Ensure a component with the configured label exists.
+    pub fn check(&self) -> Vec<String> {
+        let mut sys = System::new();
+        sys.refresh_components_list();
+        if sys.components().iter().any(|c| c.label() == self.label) {
+            vec![]
+        } else {
+            vec![format!("no component found with label: {}", self.label)]
+        }
+    }
+
+    fn get_icon(temp: u32) -> &'static str {
         match temp {
-            0..=59 => ("", None),
-            60..=69 => ("", Some(theme.yellow)),
-            70..=79 => ("", Some(theme.yellow)),
-            80..=89 => ("", Some(theme.orange)),
-            90..=u32::MAX => ("", Some(theme.red)),
+            0..=59 => "",
+            60..=69 => "",
+            70..=79 => "",
+            80..=89 => "",
+            90..=u32::MAX => "",
         }
     }
 }
@@ -59,18 +83,19 @@ impl BarItem for Sensors {
                 }
             };
 
-            let (icon, color) = Self::get_icon(&ctx.config.theme, temp as u32);
+            let icon = Self::get_icon(temp as u32);
+            let placeholders = Placeholders::new()
+                .float("temp", temp, &self.float_fmt)
+                .text("icon", icon);
+            let threshold_temp = temp as f64;
             let temp = float(temp, &self.float_fmt);
-            let mut item = I3Item::new(format!("{} {}°C", icon, temp))
+            let item = I3Item::new(format!("{} {}°C", icon, temp))
                 .short_text(format!("{}C", temp))
                 .markup(I3Markup::Pango);
+            let item = self.thresholds.apply(&ctx.theme(), &threshold_temp, item);
 
-            if let Some(color) = color {
-                item = item.color(color);
-            }
-
-            ctx.update_item(item).await?;
-            sleep(self.interval).await;
+            ctx.update_item_with(item, placeholders).await?;
+            ctx.wait_for_event(Some(self.interval)).await;
         }
     }
 }
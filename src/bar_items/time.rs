@@ -2,28 +2,57 @@ use std::error::Error;
 use std::time::Duration;
 
 use async_trait::async_trait;
+use chrono::format::{Item as FormatItem, StrftimeItems};
 use chrono::prelude::*;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 
+use crate::config::schema::DurationSchema;
 use crate::context::{BarEvent, BarItem, Context, StopAction};
 use crate::i3::{I3Button, I3Item, I3Markup};
-use crate::util::exec;
+use crate::util::{exec, Template};
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Time {
     #[serde(with = "crate::human_time")]
+    #[schemars(with = "DurationSchema")]
     interval: Duration,
     format_long: String,
-    format_short: String,
+    #[serde(default)]
+    format_short: Option<String>,
+}
+
+impl Time {
+    /// `format_short` is also a common option, which captures it when the config is parsed. If it's
+    /// a strftime format (i.e., it has no placeholders) rather than a template, then take it back.
+    pub fn take_format_short(&mut self, format_short: &mut Option<Template>) {
+        if format_short
+            .as_ref()
+            .map_or(false, |t| !t.has_placeholders())
+        {
+            self.format_short = format_short.take().map(String::from);
+        }
+    }
+
+    /// Ensure the time formats are valid.
+    pub fn check(&self) -> Vec<String> {
+        [Some(&self.format_long), self.format_short.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter(|fmt| StrftimeItems::new(fmt).any(|i| matches!(i, FormatItem::Error)))
+            .map(|fmt| format!("invalid time format: {}", fmt))
+            .collect()
+    }
 }
 
 #[async_trait(?Send)]
 impl BarItem for Time {
     async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let format_short = self.format_short.as_deref().unwrap_or_default();
         loop {
             let now = Local::now();
             let item = I3Item::new(format!("󰥔 {}", now.format(&self.format_long)))
-                .short_text(now.format(&self.format_short).to_string())
+                .short_text(now.format(format_short).to_string())
                 .markup(I3Markup::Pango);
 
             ctx.update_item(item).await?;
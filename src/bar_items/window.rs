@@ -0,0 +1,101 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::context::{BarItem, Context, StopAction};
+use crate::i3::wm::{I3EventType, I3Ipc, I3Node};
+use crate::i3::I3Item;
+use crate::util::Placeholders;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Window {
+    /// The maximum number of characters of the title to show, longer titles are truncated.
+    #[serde(default = "Window::default_max_chars")]
+    max_chars: usize,
+    /// The same as `max_chars`, but for the short text.
+    #[serde(default = "Window::default_max_chars_short")]
+    max_chars_short: usize,
+}
+
+impl Window {
+    fn default_max_chars() -> usize {
+        80
+    }
+
+    fn default_max_chars_short() -> usize {
+        20
+    }
+
+    fn item(&self, window: Option<&I3Node>) -> (I3Item, Placeholders) {
+        let (title, app) = match window {
+            Some(window) if window.is_window() => (
+                window.name.as_deref().unwrap_or_default(),
+                window.app().unwrap_or_default(),
+            ),
+            // nothing's focused, or it's an empty workspace or a container of windows
+            _ => return (I3Item::empty(), Placeholders::new()),
+        };
+
+        let item = I3Item::new(truncate(title, self.max_chars))
+            .short_text(truncate(title, self.max_chars_short));
+        let placeholders = Placeholders::new().text("title", title).text("app", app);
+
+        (item, placeholders)
+    }
+}
+
+/// Truncate text to the given number of characters, including the ellipsis added when it's truncated.
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.into();
+    }
+
+    let mut s = text
+        .chars()
+        .take(max_chars.saturating_sub(1))
+        .collect::<String>();
+    s.push('…');
+    s
+}
+
+#[async_trait(?Send)]
+impl BarItem for Window {
+    async fn start(&self, mut ctx: Context) -> Result<StopAction, Box<dyn Error>> {
+        let mut events = I3Ipc::connect()
+            .await?
+            .subscribe(&[I3EventType::Window, I3EventType::Workspace])
+            .await?;
+        let mut ipc = I3Ipc::connect().await?;
+
+        loop {
+            // rather than tracking changes from each event, the tree is fetched each time, since
+            // focus changes happen both from window events and workspace events (e.g., focusing an
+            // empty workspace)
+            let tree = ipc.get_tree().await?;
+            let (item, placeholders) = self.item(tree.find_focused());
+            ctx.update_item_with(item, placeholders).await?;
+
+            tokio::select! {
+                event = events.recv() => if event.is_none() {
+                    bail!("window manager event stream ended");
+                },
+                Some(_) = ctx.wait_for_event(None) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_title() {
+        assert_eq!(truncate("short", 5), "short");
+        assert_eq!(truncate("longer title", 6), "longe…");
+        assert_eq!(truncate("ünïcödé", 4), "ünï…");
+        assert_eq!(truncate("anything", 0), "…");
+    }
+}
@@ -1,12 +1,15 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+
+use crate::output::OutputKind;
 
 /// A lightweight and batteries-included status_command for i3 and sway.
 ///
 /// To learn more about configuration options and their possible values, see the `sample_config.toml`
 /// that's provided with this program.
-#[derive(Debug, Parser)]
+#[derive(Debug, Clone, Parser)]
 #[clap(author, version, about, long_about)]
 pub struct Cli {
     /// Path to an alternate configuration file.
@@ -15,6 +18,42 @@ pub struct Cli {
     /// Path to the socket to use for ipc. Takes precedence over the same option in the config file.
     #[clap(long)]
     pub socket: Option<PathBuf>,
+    /// The format to print the bar in, depending on which status bar it's used with.
+    /// Takes precedence over the same option in the config file.
+    #[clap(long, value_enum)]
+    pub output: Option<OutputKind>,
+    /// Only run the item with this name, e.g.: for waybar where each item is shown in its own module.
+    /// Unless `--socket` is given, the item's name is added to the end of the socket's path.
+    #[clap(long)]
+    pub item: Option<String>,
+    /// Check the configuration file for problems and exit, rather than starting the bar.
+    /// Every problem found is printed, and the exit code is non-zero if there were any.
+    #[clap(long)]
+    pub check_config: bool,
+    /// Start every item, print the bar once when they've all updated and exit.
+    /// Useful for shell prompts, tmux status lines or checking the configuration works.
+    #[clap(long)]
+    pub once: bool,
+    /// How long to wait for items to update when using `--once`. The bar is printed when this
+    /// expires, even if some items haven't updated yet.
+    #[clap(long, requires = "once", default_value = "2s", value_parser = humantime_serde::re::humantime::parse_duration)]
+    pub timeout: Duration,
+    /// The format to print the bar in when using `--once`.
+    #[clap(long, requires = "once", value_enum, default_value_t = OnceFormat::Text)]
+    pub format: OnceFormat,
+    /// Print a JSON Schema of the configuration file and exit.
+    /// Editors can use this to validate and autocomplete TOML, YAML and JSON configuration files.
+    #[clap(long)]
+    pub print_schema: bool,
+}
+
+/// How the bar is printed when using `--once`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OnceFormat {
+    /// Each item's text, without any formatting
+    Text,
+    /// The items as a JSON array, in the same format as the i3bar protocol
+    Json,
 }
 
 #[cfg(test)]
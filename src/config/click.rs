@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::i3::{I3Button, I3ClickEvent, I3Modifier};
+
+/// User configured click actions for an item, these take precedence over the item's own click handling.
+pub type OnClick = HashMap<ClickBinding, ClickAction>;
+
+/// A mouse button plus any modifiers, written as `shift+control+left` in the configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ClickBinding {
+    button: I3Button,
+    modifiers: Vec<I3Modifier>,
+}
+
+impl ClickBinding {
+    /// Whether the click event triggers this binding: the button must be the same, and all of the
+    /// binding's modifiers must be held. Other modifiers are ignored (e.g., `Mod2` when Num Lock is on).
+    fn matches(&self, click: &I3ClickEvent) -> bool {
+        self.button == click.button && self.modifiers.iter().all(|m| click.modifiers.contains(m))
+    }
+}
+
+impl FromStr for ClickBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('+').map(str::trim).collect::<Vec<_>>();
+        let button = match parts.pop() {
+            Some("left") => I3Button::Left,
+            Some("middle") => I3Button::Middle,
+            Some("right") => I3Button::Right,
+            Some("scroll_up") => I3Button::ScrollUp,
+            Some("scroll_down") => I3Button::ScrollDown,
+            _ => {
+                return Err(format!(
+                    "invalid click binding: {}, expected it to end with one of: left, middle, right, scroll_up, scroll_down",
+                    s
+                ))
+            }
+        };
+
+        let mut modifiers = parts
+            .into_iter()
+            .map(|m| match m {
+                "mod1" => Ok(I3Modifier::Mod1),
+                "mod2" => Ok(I3Modifier::Mod2),
+                "mod3" => Ok(I3Modifier::Mod3),
+                "mod4" => Ok(I3Modifier::Mod4),
+                "mod5" => Ok(I3Modifier::Mod5),
+                "shift" => Ok(I3Modifier::Shift),
+                "control" => Ok(I3Modifier::Control),
+                _ => Err(format!(
+                    "invalid modifier in click binding: {}, expected one of: mod1, mod2, mod3, mod4, mod5, shift, control",
+                    m
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // so `shift+control+left` and `control+shift+left` are the same binding
+        modifiers.sort_by_key(|m| *m as u8);
+        modifiers.dedup();
+
+        Ok(ClickBinding { button, modifiers })
+    }
+}
+
+impl TryFrom<String> for ClickBinding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for ClickBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for m in &self.modifiers {
+            let m = match m {
+                I3Modifier::Mod1 => "mod1",
+                I3Modifier::Mod2 => "mod2",
+                I3Modifier::Mod3 => "mod3",
+                I3Modifier::Mod4 => "mod4",
+                I3Modifier::Mod5 => "mod5",
+                I3Modifier::Shift => "shift",
+                I3Modifier::Control => "control",
+            };
+            write!(f, "{}+", m)?;
+        }
+
+        write!(
+            f,
+            "{}",
+            match self.button {
+                I3Button::Left => "left",
+                I3Button::Middle => "middle",
+                I3Button::Right => "right",
+                I3Button::ScrollUp => "scroll_up",
+                I3Button::ScrollDown => "scroll_down",
+            }
+        )
+    }
+}
+
+impl From<ClickBinding> for String {
+    fn from(value: ClickBinding) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickAction {
+    /// Run a shell command, the click's `I3_*` environment variables are set just like `script` items
+    Command(String),
+    /// Run one of the item's built-in actions
+    Action(ItemAction),
+}
+
+/// Built-in actions which can be bound to clicks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ItemAction {
+    /// `paginator:next`: show the next page, for items with multiple pages (e.g., `disk` and `nic`)
+    PaginatorNext,
+    /// `paginator:prev`: show the previous page, for items with multiple pages (e.g., `disk` and `nic`)
+    PaginatorPrev,
+    /// `<type>:<args...>`: send a custom event to the item, this is the same as running
+    /// `istat-ipc custom <item> <args...>`, e.g., `pulse:mute-toggle sink`
+    Custom { tag: String, args: Vec<String> },
+}
+
+impl ItemAction {
+    /// Ensure the action can be used with the given type of item.
+    pub fn validate(&self, tag: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            ItemAction::Custom { tag: t, .. } if t != tag => {
+                bail!("action {} can't be used with {} items", self, tag)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for ItemAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("paginator", "next")) => Ok(ItemAction::PaginatorNext),
+            Some(("paginator", "prev")) => Ok(ItemAction::PaginatorPrev),
+            Some(("paginator", other)) => Err(format!(
+                "invalid paginator action: {}, expected one of: next, prev",
+                other
+            )),
+            Some((tag, args)) if !tag.is_empty() && !args.trim().is_empty() => {
+                Ok(ItemAction::Custom {
+                    tag: tag.into(),
+                    args: args.split_whitespace().map(String::from).collect(),
+                })
+            }
+            _ => Err(format!(
+                "invalid action: {}, expected it to look like: <type>:<args>",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for ItemAction {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for ItemAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemAction::PaginatorNext => write!(f, "paginator:next"),
+            ItemAction::PaginatorPrev => write!(f, "paginator:prev"),
+            ItemAction::Custom { tag, args } => write!(f, "{}:{}", tag, args.join(" ")),
+        }
+    }
+}
+
+impl From<ItemAction> for String {
+    fn from(value: ItemAction) -> Self {
+        value.to_string()
+    }
+}
+
+/// Find the action bound to a click event. If multiple bindings match, then the one with the most
+/// modifiers is chosen, so `shift+left` takes precedence over `left` when shift is held.
+pub fn find_click_action<'a>(
+    on_click: &'a OnClick,
+    click: &I3ClickEvent,
+) -> Option<&'a ClickAction> {
+    on_click
+        .iter()
+        .filter(|(binding, _)| binding.matches(click))
+        .max_by_key(|(binding, _)| binding.modifiers.len())
+        .map(|(_, action)| action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn click(button: I3Button, modifiers: &[I3Modifier]) -> I3ClickEvent {
+        I3ClickEvent {
+            button,
+            modifiers: modifiers.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_binding() {
+        let binding = "control+shift+scroll_up".parse::<ClickBinding>().unwrap();
+        assert_eq!(binding.button, I3Button::ScrollUp);
+        assert_eq!(binding.modifiers, [I3Modifier::Shift, I3Modifier::Control]);
+        assert_eq!(binding.to_string(), "shift+control+scroll_up");
+        assert_eq!(binding, "shift+control+scroll_up".parse().unwrap());
+    }
+
+    #[test]
+    fn parse_binding_invalid() {
+        assert!("".parse::<ClickBinding>().is_err());
+        assert!("shift".parse::<ClickBinding>().is_err());
+        assert!("hyper+left".parse::<ClickBinding>().is_err());
+    }
+
+    #[test]
+    fn parse_action() {
+        assert_eq!(
+            "paginator:next".parse::<ItemAction>().unwrap(),
+            ItemAction::PaginatorNext
+        );
+        assert_eq!(
+            "pulse:mute-toggle sink".parse::<ItemAction>().unwrap(),
+            ItemAction::Custom {
+                tag: "pulse".into(),
+                args: vec!["mute-toggle".into(), "sink".into()]
+            }
+        );
+        assert!("paginator:first".parse::<ItemAction>().is_err());
+        assert!("pulse".parse::<ItemAction>().is_err());
+        assert!("pulse:".parse::<ItemAction>().is_err());
+    }
+
+    #[test]
+    fn validate_action() {
+        let action = "pulse:mute-toggle".parse::<ItemAction>().unwrap();
+        action.validate("pulse").unwrap();
+        assert!(action.validate("cpu").is_err());
+        ItemAction::PaginatorNext.validate("disk").unwrap();
+    }
+
+    #[test]
+    fn find_action() {
+        let on_click = OnClick::from([
+            (
+                "left".parse::<ClickBinding>().unwrap(),
+                ClickAction::Command("left".into()),
+            ),
+            (
+                "shift+left".parse::<ClickBinding>().unwrap(),
+                ClickAction::Command("shift+left".into()),
+            ),
+        ]);
+
+        let find = |c| match find_click_action(&on_click, &c) {
+            Some(ClickAction::Command(cmd)) => Some(cmd.as_str()),
+            _ => None,
+        };
+
+        assert_eq!(find(click(I3Button::Left, &[])), Some("left"));
+        assert_eq!(
+            find(click(I3Button::Left, &[I3Modifier::Mod2])),
+            Some("left")
+        );
+        assert_eq!(
+            find(click(I3Button::Left, &[I3Modifier::Shift])),
+            Some("shift+left")
+        );
+        assert_eq!(
+            find(click(
+                I3Button::Left,
+                &[I3Modifier::Shift, I3Modifier::Mod2]
+            )),
+            Some("shift+left")
+        );
+        assert_eq!(find(click(I3Button::Right, &[])), None);
+    }
+}
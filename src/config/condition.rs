@@ -0,0 +1,369 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::util::net::Interface;
+use crate::util::Placeholders;
+
+/// A condition used to decide whether an item should be shown in the bar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// An expression over the item's own values, e.g.: `pct < 20`
+    Expr(Expression),
+    /// A shell command, which is true if it exits successfully
+    Command(String),
+    /// True if the file exists
+    File(PathBuf),
+    /// True if the network interface exists and has an address
+    Interface(String),
+}
+
+impl Condition {
+    /// How long a `command` condition may run before it's killed, and treated as false.
+    const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Whether the condition depends on the item's values. Conditions which don't are re-evaluated
+    /// on an interval, rather than each time the item updates.
+    pub fn uses_item(&self) -> bool {
+        matches!(self, Condition::Expr(_))
+    }
+
+    pub async fn evaluate(&self, placeholders: &Placeholders) -> bool {
+        match self {
+            Condition::Expr(expr) => expr.evaluate(placeholders),
+            Condition::Command(cmd) => {
+                let child = Command::new("sh")
+                    .arg("-c")
+                    .arg(cmd)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .spawn();
+
+                let result = match child {
+                    Ok(mut child) => timeout(Self::COMMAND_TIMEOUT, child.wait()).await,
+                    Err(e) => Ok(Err(e)),
+                };
+
+                match result {
+                    Ok(Ok(status)) => status.success(),
+                    Ok(Err(e)) => {
+                        log::error!("fail: condition command --> {} <-- {}", cmd, e);
+                        false
+                    }
+                    Err(_) => {
+                        log::warn!("condition command timed out: {}", cmd);
+                        false
+                    }
+                }
+            }
+            Condition::File(path) => tokio::fs::try_exists(path).await.unwrap_or(false),
+            Condition::Interface(name) => match Interface::get_interfaces() {
+                Ok(interfaces) => interfaces.iter().any(|i| i.name == *name),
+                Err(e) => {
+                    log::error!("failed to read interfaces for condition: {}", e);
+                    false
+                }
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn compare<T: PartialOrd>(&self, a: T, b: T) -> bool {
+        match self {
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Number(f64),
+    /// A quoted string
+    Text(String),
+    /// Either the name of one of the item's values, or a string if the item has no such value
+    Word(String),
+}
+
+impl Operand {
+    fn number(&self, placeholders: &Placeholders) -> Option<f64> {
+        match self {
+            Operand::Number(n) => Some(*n),
+            Operand::Text(s) => s.parse().ok(),
+            Operand::Word(w) => placeholders.number(w).or_else(|| w.parse().ok()),
+        }
+    }
+
+    fn text(&self, placeholders: &Placeholders) -> String {
+        match self {
+            Operand::Number(n) => n.to_string(),
+            Operand::Text(s) => s.clone(),
+            Operand::Word(w) => placeholders.text_value(w).unwrap_or_else(|| w.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Comparison {
+    lhs: Operand,
+    op: Op,
+    rhs: Operand,
+}
+
+impl Comparison {
+    /// Values are compared as numbers when both sides are numbers, otherwise they're compared as text.
+    fn evaluate(&self, placeholders: &Placeholders) -> bool {
+        match (self.lhs.number(placeholders), self.rhs.number(placeholders)) {
+            (Some(a), Some(b)) => self.op.compare(a, b),
+            _ => self
+                .op
+                .compare(self.lhs.text(placeholders), self.rhs.text(placeholders)),
+        }
+    }
+}
+
+/// A simple expression made of comparisons joined by `and` and `or`, where `and` binds more tightly.
+/// For example: `down_bytes < minimum and up_bytes < minimum`, or `state == charging or pct > 90`.
+///
+/// Each side of a comparison is either a number, a quoted string, or the name of one of the item's
+/// values (the same ones available to `format`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Expression {
+    source: String,
+    /// Groups of comparisons which are joined with `or`, and each group is joined with `and`
+    any: Vec<Vec<Comparison>>,
+}
+
+impl Expression {
+    pub fn evaluate(&self, placeholders: &Placeholders) -> bool {
+        self.any
+            .iter()
+            .any(|all| all.iter().all(|c| c.evaluate(placeholders)))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Op(Op),
+    Operand(Operand),
+    And,
+    Or,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let is_op = |c: char| matches!(c, '<' | '>' | '=' | '!');
+
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if is_op(c) {
+            let mut op = String::new();
+            while let Some(c) = chars.next_if(|c| is_op(*c)) {
+                op.push(c);
+            }
+            tokens.push(Token::Op(match op.as_str() {
+                "<" => Op::Lt,
+                "<=" => Op::Le,
+                ">" => Op::Gt,
+                ">=" => Op::Ge,
+                "==" => Op::Eq,
+                "!=" => Op::Ne,
+                _ => return Err(format!("invalid operator: {}", op)),
+            }));
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some(q) if q == c => break,
+                    Some(c) => text.push(c),
+                    None => return Err(format!("unclosed quote in expression: {}", s)),
+                }
+            }
+            tokens.push(Token::Operand(Operand::Text(text)));
+        } else {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !is_op(*c)) {
+                word.push(c);
+            }
+            tokens.push(match word.as_str() {
+                "and" => Token::And,
+                "or" => Token::Or,
+                _ => Token::Operand(match word.parse::<f64>() {
+                    Ok(n) => Operand::Number(n),
+                    Err(_) => Operand::Word(word),
+                }),
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+impl FromStr for Expression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid expression: {}, expected comparisons like `a < b` joined with `and` or `or`",
+                s
+            )
+        };
+
+        let mut any = vec![vec![]];
+        let mut tokens = tokenize(s)?.into_iter();
+        loop {
+            let comparison = match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some(Token::Operand(lhs)), Some(Token::Op(op)), Some(Token::Operand(rhs))) => {
+                    Comparison { lhs, op, rhs }
+                }
+                _ => return Err(invalid()),
+            };
+
+            // SAFETY: there's always at least one group
+            any.last_mut().unwrap().push(comparison);
+            match tokens.next() {
+                None => break,
+                Some(Token::And) => {}
+                Some(Token::Or) => any.push(vec![]),
+                Some(_) => return Err(invalid()),
+            }
+        }
+
+        Ok(Expression {
+            source: s.into(),
+            any,
+        })
+    }
+}
+
+impl TryFrom<String> for Expression {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl From<Expression> for String {
+    fn from(value: Expression) -> Self {
+        value.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::FloatFormat;
+
+    fn eval(expr: &str, placeholders: &Placeholders) -> bool {
+        expr.parse::<Expression>().unwrap().evaluate(placeholders)
+    }
+
+    #[test]
+    fn parse() {
+        let expr = "pct<20 or state == 'not charging' and pct != 1.5"
+            .parse::<Expression>()
+            .unwrap();
+        assert_eq!(
+            expr.any,
+            [
+                vec![Comparison {
+                    lhs: Operand::Word("pct".into()),
+                    op: Op::Lt,
+                    rhs: Operand::Number(20.0)
+                }],
+                vec![
+                    Comparison {
+                        lhs: Operand::Word("state".into()),
+                        op: Op::Eq,
+                        rhs: Operand::Text("not charging".into())
+                    },
+                    Comparison {
+                        lhs: Operand::Word("pct".into()),
+                        op: Op::Ne,
+                        rhs: Operand::Number(1.5)
+                    }
+                ]
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!("".parse::<Expression>().is_err());
+        assert!("pct".parse::<Expression>().is_err());
+        assert!("pct <".parse::<Expression>().is_err());
+        assert!("pct => 1".parse::<Expression>().is_err());
+        assert!("pct < 1 and".parse::<Expression>().is_err());
+        assert!("pct < 1 2".parse::<Expression>().is_err());
+        assert!("state == 'full".parse::<Expression>().is_err());
+    }
+
+    #[test]
+    fn evaluate() {
+        let fmt = FloatFormat::default();
+        let p = Placeholders::new()
+            .float("pct", 15.0, &fmt)
+            .float("minimum", 1024.0, &fmt)
+            .text("state", "full")
+            .text("quality", "80");
+
+        assert!(eval("pct < 20", &p));
+        assert!(!eval("pct >= 20", &p));
+        assert!(eval("pct < minimum", &p));
+        assert!(eval("quality >= 80", &p));
+        assert!(eval("state == full", &p));
+        assert!(eval("state == 'full' and pct <= 15", &p));
+        assert!(eval("pct > 50 or state != charging", &p));
+        assert!(!eval("pct > 50 or state == charging and pct < 20", &p));
+    }
+
+    #[tokio::test]
+    async fn evaluate_condition() {
+        let p = Placeholders::new();
+        assert!(Condition::Command("true".into()).evaluate(&p).await);
+        assert!(!Condition::Command("false".into()).evaluate(&p).await);
+        assert!(Condition::File("/".into()).evaluate(&p).await);
+        assert!(
+            !Condition::File("/this/does/not/exist".into())
+                .evaluate(&p)
+                .await
+        );
+    }
+}
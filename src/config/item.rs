@@ -1,13 +1,19 @@
 use std::cell::OnceCell;
 
+use schemars::JsonSchema;
+use serde::Deserializer;
 use serde_derive::{Deserialize, Serialize};
 use strum::EnumIter;
 
+use super::click::OnClick;
+use super::condition::Condition;
 use crate::bar_items::*;
 use crate::context::BarItem;
 use crate::i3::I3Item;
+use crate::theme::ThemeOverride;
+use crate::util::Template;
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Common {
     /// Name of the item. Used in the IPC protocol.
     /// Defaults to the item's type.
@@ -18,25 +24,48 @@ pub struct Common {
     pub signal: Option<u32>,
     /// Optionally set or unset the separator for this item.
     pub separator: Option<bool>,
+    /// Run a command or a built-in action when the item is clicked, for example:
+    /// `on_click."shift+left" = { command = "pavucontrol" }` or `on_click.middle = { action = "pulse:mute-toggle sink" }`
+    /// These take precedence over the item's own click handling.
+    #[serde(default)]
+    pub on_click: OnClick,
+    /// Override the item's text with a template, e.g.: `format = "CPU {pct:.1}%"`.
+    /// See the documentation for the placeholders each item provides.
+    pub format: Option<Template>,
+    /// The same as `format`, but for the item's short text.
+    pub format_short: Option<Template>,
+    /// Hide the item when this condition is true, e.g.: `hide_when = { expr = "pct < 20" }`.
+    pub hide_when: Option<Condition>,
+    /// Only show the item when this condition is true, e.g.: `show_when = { interface = "vpn0" }`.
+    pub show_when: Option<Condition>,
+    /// Override colours of the global theme for this item, e.g.: `theme.red = "#ff0000"`.
+    pub theme: Option<ThemeOverride>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, EnumIter)]
+#[derive(Debug, Serialize, Deserialize, Clone, EnumIter, JsonSchema)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ItemInner {
     Raw(I3Item),
+    Backlight(Backlight),
     Battery(Battery),
+    Bluetooth(Bluetooth),
     Cpu(Cpu),
     Disk(Disk),
     Dunst(Dunst),
     Kbd(Kbd),
+    KbdLayout(KbdLayout),
     Krb(Krb),
     Mem(Mem),
+    Mode(Mode),
+    Mpris(Mpris),
     NetUsage(NetUsage),
     Nic(Nic),
     Pulse(Pulse),
+    Scratchpad(Scratchpad),
     Script(Script),
     Sensors(Sensors),
     Time(Time),
+    Window(Window),
 }
 
 impl ItemInner {
@@ -46,24 +75,31 @@ impl ItemInner {
     pub fn tag(&self) -> &'static str {
         match self {
             ItemInner::Raw(_) => "raw",
+            ItemInner::Backlight(_) => "backlight",
             ItemInner::Battery(_) => "battery",
+            ItemInner::Bluetooth(_) => "bluetooth",
             ItemInner::Cpu(_) => "cpu",
             ItemInner::Disk(_) => "disk",
             ItemInner::Dunst(_) => "dunst",
             ItemInner::Kbd(_) => "kbd",
+            ItemInner::KbdLayout(_) => "kbd_layout",
             ItemInner::Krb(_) => "krb",
             ItemInner::Mem(_) => "mem",
+            ItemInner::Mode(_) => "mode",
+            ItemInner::Mpris(_) => "mpris",
             ItemInner::NetUsage(_) => "net_usage",
             ItemInner::Nic(_) => "nic",
             ItemInner::Pulse(_) => "pulse",
+            ItemInner::Scratchpad(_) => "scratchpad",
             ItemInner::Script(_) => "script",
             ItemInner::Sensors(_) => "sensors",
             ItemInner::Time(_) => "time",
+            ItemInner::Window(_) => "window",
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct Item {
     #[serde(flatten)]
     pub common: Common,
@@ -75,23 +111,80 @@ pub struct Item {
     name: OnceCell<String>,
 }
 
+/// The options as they're written in the config, see `Item`'s `Deserialize` implementation.
+#[derive(Deserialize)]
+struct ItemConfig {
+    #[serde(flatten)]
+    common: Common,
+    #[serde(flatten)]
+    inner: ItemInner,
+}
+
+impl<'de> serde::Deserialize<'de> for Item {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ItemConfig {
+            mut common,
+            mut inner,
+        } = <ItemConfig as serde::Deserialize>::deserialize(deserializer)?;
+
+        // the common options are deserialised first, so they capture options which share a name
+        if let ItemInner::Time(time) = &mut inner {
+            time.take_format_short(&mut common.format_short);
+        }
+
+        Ok(Item {
+            common,
+            inner,
+            name: OnceCell::new(),
+        })
+    }
+}
+
 impl Item {
     pub fn to_bar_item(&self) -> Box<dyn BarItem> {
         match &self.inner {
             ItemInner::Raw(inner) => Box::new(inner.clone()),
+            ItemInner::Backlight(inner) => Box::new(inner.clone()),
             ItemInner::Battery(inner) => Box::new(inner.clone()),
+            ItemInner::Bluetooth(inner) => Box::new(inner.clone()),
             ItemInner::Cpu(inner) => Box::new(inner.clone()),
             ItemInner::Disk(inner) => Box::new(inner.clone()),
             ItemInner::Dunst(inner) => Box::new(inner.clone()),
             ItemInner::Kbd(inner) => Box::new(inner.clone()),
+            ItemInner::KbdLayout(inner) => Box::new(inner.clone()),
             ItemInner::Krb(inner) => Box::new(inner.clone()),
             ItemInner::Mem(inner) => Box::new(inner.clone()),
+            ItemInner::Mode(inner) => Box::new(inner.clone()),
+            ItemInner::Mpris(inner) => Box::new(inner.clone()),
             ItemInner::NetUsage(inner) => Box::new(inner.clone()),
             ItemInner::Nic(inner) => Box::new(inner.clone()),
             ItemInner::Pulse(inner) => Box::new(inner.clone()),
+            ItemInner::Scratchpad(inner) => Box::new(inner.clone()),
             ItemInner::Script(inner) => Box::new(inner.clone()),
             ItemInner::Sensors(inner) => Box::new(inner.clone()),
             ItemInner::Time(inner) => Box::new(inner.clone()),
+            ItemInner::Window(inner) => Box::new(inner.clone()),
+        }
+    }
+
+    pub fn tag(&self) -> &'static str {
+        self.inner.tag()
+    }
+
+    /// Check the item's own options for problems that can't be caught when parsing the configuration,
+    /// such as sensors or batteries which don't exist.
+    pub fn check(&self) -> Vec<String> {
+        match &self.inner {
+            ItemInner::Backlight(inner) => inner.check(),
+            ItemInner::Battery(inner) => inner.check(),
+            ItemInner::Nic(inner) => inner.check(),
+            ItemInner::Script(inner) => inner.check(),
+            ItemInner::Sensors(inner) => inner.check(),
+            ItemInner::Time(inner) => inner.check(),
+            _ => vec![],
         }
     }
 
@@ -139,4 +232,54 @@ mod tests {
             assert_tag(&variant);
         }
     }
+
+    #[test]
+    fn raw_item_common_options() {
+        let item = serde_json::from_value::<Item>(json!({
+            "type": "raw",
+            "full_text": "raw",
+            "index": 0,
+            "format": "[{full_text}]",
+        }))
+        .unwrap();
+
+        assert_eq!(item.common.index, Some(0));
+        assert_eq!(item.common.format.unwrap().to_string(), "[{full_text}]");
+        // common options shouldn't end up as additional data in the raw item
+        assert_eq!(
+            json!(item.inner),
+            json!({ "type": "raw", "full_text": "raw" })
+        );
+    }
+
+    #[test]
+    fn time_item_format_short() {
+        // `format_short` is both a common option and a `time` option, strftime formats go to the item
+        let item = serde_json::from_value::<Item>(json!({
+            "type": "time",
+            "interval": "1s",
+            "format_long": "%Y-%m-%d",
+            "format_short": "%H:%M",
+        }))
+        .unwrap();
+
+        assert_eq!(item.common.format_short, None);
+        assert_eq!(json!(item.inner)["format_short"], "%H:%M");
+        assert_eq!(item.tag(), "time");
+
+        // and templates stay as the common option
+        let item = serde_json::from_value::<Item>(json!({
+            "type": "time",
+            "interval": "1s",
+            "format_long": "%Y-%m-%d",
+            "format_short": "[{short_text}]",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            item.common.format_short.unwrap().to_string(),
+            "[{short_text}]"
+        );
+        assert_eq!(json!(item.inner)["format_short"], json!(null));
+    }
 }
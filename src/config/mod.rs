@@ -1,20 +1,27 @@
+pub mod click;
+pub mod condition;
 mod item;
 mod parse;
+pub mod schema;
 
 use std::cell::OnceCell;
 use std::error::Error;
 use std::path::PathBuf;
 
 use indexmap::IndexMap;
+use libc::{SIGRTMAX, SIGRTMIN};
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 
+use self::click::ClickAction;
+pub use self::item::{Common, Item};
 use crate::cli::Cli;
-use crate::config::item::Item;
 use crate::ipc::get_socket_path;
+use crate::output::OutputKind;
 use crate::theme::Theme;
 use crate::util::sort_by_indices;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AppConfig {
     /// Optional list of paths of other configuration files to include.
     /// The paths can be absolute or relative to the main configuration file's directory.
@@ -33,6 +40,11 @@ pub struct AppConfig {
     /// The CLI option takes precedence over this.
     #[serde(rename = "socket")]
     socket: Option<PathBuf>,
+
+    /// Which status bar the bar is printed for: `i3bar` (the default, and also for swaybar), `waybar`,
+    /// `polybar`, `lemonbar` or `plain`. The CLI option takes precedence over this.
+    #[serde(default)]
+    pub output: OutputKind,
     /// Runtime only cache for the resolved socket path.
 
     /// Runtime only cache for index to name item mappings
@@ -99,17 +111,85 @@ impl AppConfig {
         Ok(())
     }
 
-    pub async fn read(args: Cli) -> Result<AppConfig, Box<dyn Error>> {
-        let mut cfg = parse::parse(&args)?;
+    /// Ensure the item's signal (if any) is a valid realtime signal.
+    fn check_signal(item: &Item) -> Result<(), String> {
+        // signals are passed in from 0..(SIGRTMAX - SIGRTMIN)
+        let max = (SIGRTMAX() - SIGRTMIN()) as u32;
+        match item.common.signal {
+            Some(sig) if sig > max => Err(format!(
+                "invalid signal: {}. Valid signals range from 0 up to {} inclusive",
+                sig, max
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Ensure the item's built-in click actions can be used with it.
+    fn check_on_click(item: &Item) -> Result<(), String> {
+        for (binding, action) in &item.common.on_click {
+            if let ClickAction::Action(action) = action {
+                if let Err(e) = action.validate(item.tag()) {
+                    return Err(format!("invalid on_click.{}: {}", binding, e));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensure any signals configured for items are valid realtime signals.
+    fn validate_signals(items: &[Item]) -> Result<(), Box<dyn Error>> {
+        for (idx, item) in items.iter().enumerate() {
+            if let Err(e) = Self::check_signal(item) {
+                bail!("item[{}] has an {}", idx, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensure any built-in click actions can be used with their items.
+    fn validate_on_click(items: &[Item]) -> Result<(), Box<dyn Error>> {
+        for (idx, item) in items.iter().enumerate() {
+            if let Err(e) = Self::check_on_click(item) {
+                bail!("item[{}] has an {}", idx, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn read(args: &Cli) -> Result<AppConfig, Box<dyn Error>> {
+        let mut cfg = parse::parse(args)?;
 
         // set socket path explicitly here
         // NOTE: this workaround exists due to a limitation in serde
         // see: https://github.com/serde-rs/serde/issues/2249
-        cfg.socket = Some(match args.socket {
-            Some(socket_path) => socket_path,
-            None => get_socket_path(cfg.socket.as_ref())?,
+        cfg.socket = Some(match &args.socket {
+            Some(socket_path) => socket_path.clone(),
+            None => {
+                let socket_path = match get_socket_path(cfg.socket.as_ref()) {
+                    Ok(socket_path) => socket_path,
+                    // no socket is created when only printing the bar once, so i3 needn't be running
+                    Err(_) if args.once => PathBuf::new(),
+                    Err(e) => return Err(e),
+                };
+                // so multiple instances running a single item each don't share the same socket
+                match &args.item {
+                    Some(name) => {
+                        let mut socket_path = socket_path.into_os_string();
+                        socket_path.push(format!(".{}", name));
+                        socket_path.into()
+                    }
+                    None => socket_path,
+                }
+            }
         });
 
+        if let Some(output) = args.output {
+            cfg.output = output;
+        }
+
         // config validation
         {
             // sort items as defined in the configuration
@@ -118,10 +198,96 @@ impl AppConfig {
             // check no duplicate names
             Self::validate_names(&cfg.items)?;
 
+            // check all signals are valid
+            Self::validate_signals(&cfg.items)?;
+
+            // check all click actions are valid
+            Self::validate_on_click(&cfg.items)?;
+
             // check no empty powerline config
             cfg.theme.validate()?;
         }
 
+        // only run a single item if asked to
+        if let Some(name) = &args.item {
+            cfg.items.retain(|item| item.name() == name);
+            if cfg.items.is_empty() {
+                bail!("no item found with name: {}", name);
+            }
+        }
+
+        Ok(cfg)
+    }
+
+    /// Check the configuration without starting the bar, used by `--check-config`.
+    /// Unlike `read`, this doesn't stop at the first problem: every problem that's found is returned.
+    pub fn check(args: &Cli) -> Vec<String> {
+        let mut cfg = match parse::parse(args) {
+            Ok(cfg) => cfg,
+            // nothing else can be checked if the configuration couldn't be parsed
+            Err(e) => return vec![e.to_string()],
+        };
+
+        let mut problems = vec![];
+
+        // sort items first, so the reported indices match their positions in the bar
+        Self::sort(&mut cfg.items);
+
+        if let Err(e) = Self::validate_names(&cfg.items) {
+            problems.push(e.to_string());
+        }
+
+        if let Err(e) = cfg.theme.validate() {
+            problems.push(e.to_string());
+        }
+
+        for (idx, item) in cfg.items.iter().enumerate() {
+            let item_problems = [Self::check_signal(item), Self::check_on_click(item)]
+                .into_iter()
+                .filter_map(Result::err)
+                .chain(item.check());
+
+            for problem in item_problems {
+                problems.push(format!("item[{}] ({}): {}", idx, item.name(), problem));
+            }
+        }
+
+        problems
+    }
+
+    /// Create a copy of this configuration with a different list of items, used when items are changed
+    /// at runtime. The items are validated, but not sorted, since they're already in the desired order.
+    pub fn with_items(&self, items: Vec<Item>) -> Result<AppConfig, Box<dyn Error>> {
+        Self::validate_names(&items)?;
+        Self::validate_signals(&items)?;
+        Self::validate_on_click(&items)?;
+
+        Ok(AppConfig {
+            include: self.include.clone(),
+            theme: self.theme.clone(),
+            items,
+            socket: self.socket.clone(),
+            output: self.output,
+            idx_to_name: OnceCell::new(),
+        })
+    }
+
+    /// Read the configuration again, keeping any options which can't be changed at runtime.
+    pub async fn reload(&self, args: &Cli) -> Result<AppConfig, Box<dyn Error>> {
+        let mut cfg = Self::read(args).await?;
+
+        // the socket has already been bound, so it can't be changed without a restart
+        if cfg.socket != self.socket {
+            log::warn!("changing the socket path requires a restart, ignoring new socket path");
+            cfg.socket = self.socket.clone();
+        }
+
+        // the same goes for the output, since the bar's header has already been printed
+        if cfg.output != self.output {
+            log::warn!("changing the output requires a restart, ignoring new output");
+            cfg.output = self.output;
+        }
+
         Ok(cfg)
     }
 }
@@ -129,6 +295,7 @@ impl AppConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::click::ClickBinding;
     use crate::config::item::Common;
     use crate::i3::I3Item;
 
@@ -172,6 +339,74 @@ mod tests {
         AppConfig::validate_names(&[item!("a"), item!("c"), item!("d"), item!("c")]).unwrap();
     }
 
+    #[test]
+    fn validate_signals() {
+        let item = |signal| {
+            Item::new(
+                Common {
+                    signal: Some(signal),
+                    ..Default::default()
+                },
+                I3Item::empty(),
+            )
+        };
+
+        AppConfig::validate_signals(&[item!(), item(0), item(1)]).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "item[1] has an invalid signal: 9001")]
+    fn validate_signals_invalid() {
+        let item = Item::new(
+            Common {
+                signal: Some(9001),
+                ..Default::default()
+            },
+            I3Item::empty(),
+        );
+
+        AppConfig::validate_signals(&[item!(), item]).unwrap();
+    }
+
+    #[test]
+    fn validate_on_click() {
+        let item = |action: &str| {
+            Item::new(
+                Common {
+                    on_click: [(
+                        "left".parse::<ClickBinding>().unwrap(),
+                        ClickAction::Action(action.parse().unwrap()),
+                    )]
+                    .into(),
+                    ..Default::default()
+                },
+                I3Item::empty(),
+            )
+        };
+
+        AppConfig::validate_on_click(&[item!(), item("raw:foo"), item("paginator:next")]).unwrap();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "item[1] has an invalid on_click.left: action pulse:mute-toggle can't be used with raw items"
+    )]
+    fn validate_on_click_invalid() {
+        let item = Item::new(
+            Common {
+                on_click: [(
+                    "left".parse::<ClickBinding>().unwrap(),
+                    ClickAction::Action("pulse:mute-toggle".parse().unwrap()),
+                )]
+                .into(),
+                ..Default::default()
+            },
+            I3Item::empty(),
+        );
+
+        AppConfig::validate_on_click(&[item!(), item]).unwrap();
+    }
+
     macro_rules! to_names {
         ($items:expr) => {
             $items
@@ -215,4 +450,37 @@ mod tests {
         AppConfig::sort(&mut items);
         assert_eq!(to_names!(items), ["a", "b", "c"]);
     }
+
+    #[test]
+    fn with_items() {
+        let config = AppConfig {
+            include: vec![],
+            theme: Theme::default(),
+            items: vec![item!("a")],
+            socket: None,
+            output: OutputKind::default(),
+            idx_to_name: OnceCell::new(),
+        };
+        assert_eq!(config.item_idx_to_name()[&0], "a");
+
+        let config = config.with_items(vec![item!("b"), item!("a")]).unwrap();
+        assert_eq!(to_names!(&config.items), ["b", "a"]);
+        assert_eq!(config.item_idx_to_name()[&0], "b");
+        assert_eq!(config.item_idx_to_name()[&1], "a");
+    }
+
+    #[test]
+    #[should_panic(expected = "item names must be unique")]
+    fn with_items_duplicate() {
+        let config = AppConfig {
+            include: vec![],
+            theme: Theme::default(),
+            items: vec![item!("a")],
+            socket: None,
+            output: OutputKind::default(),
+            idx_to_name: OnceCell::new(),
+        };
+
+        config.with_items(vec![item!("a"), item!("a")]).unwrap();
+    }
 }
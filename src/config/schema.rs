@@ -0,0 +1,195 @@
+//! A JSON Schema for the configuration, so editors can validate and autocomplete configuration files.
+//!
+//! Most types derive `JsonSchema`, but types which are written as strings in the configuration (and
+//! types from other crates) have their schemas defined here.
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{
+    InstanceType,
+    Metadata,
+    RootSchema,
+    Schema,
+    SchemaObject,
+    StringValidation,
+};
+use schemars::{schema_for, JsonSchema};
+
+use super::click::{ClickBinding, ItemAction};
+use super::condition::Expression;
+use super::AppConfig;
+use crate::bar_items::InterfaceFilter;
+use crate::theme::ThemeColor;
+use crate::util::Template;
+
+/// Generate the JSON Schema for the configuration file.
+pub fn schema() -> RootSchema {
+    schema_for!(AppConfig)
+}
+
+fn string_schema(description: &str, pattern: Option<&str>) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.into()),
+            ..Default::default()
+        })),
+        string: pattern.map(|pattern| {
+            Box::new(StringValidation {
+                pattern: Some(pattern.into()),
+                ..Default::default()
+            })
+        }),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Implement `JsonSchema` for a type which is written as a string in the configuration.
+macro_rules! impl_string_schema {
+    ($ty:ty, $description:expr) => {
+        impl_string_schema!($ty, $description, None);
+    };
+    ($ty:ty, $description:expr, $pattern:expr) => {
+        impl JsonSchema for $ty {
+            fn schema_name() -> String {
+                stringify!($ty).into()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                string_schema($description, $pattern)
+            }
+        }
+    };
+}
+
+/// Used with `#[schemars(with = "...")]` for durations, which are parsed with `humantime`.
+pub struct DurationSchema;
+/// Used with `#[schemars(with = "...")]` for hex colours.
+pub struct HexColorSchema;
+/// Used with `#[schemars(with = "...")]` for byte sizes.
+pub struct ByteSizeSchema;
+
+impl_string_schema!(
+    DurationSchema,
+    "A duration, e.g.: `1s`, `500ms` or `1h 30m`",
+    Some(r"^\s*\d")
+);
+impl_string_schema!(
+    HexColorSchema,
+    "A hex colour, e.g.: `#ff0000` or `#ff000080`",
+    Some("^#([0-9a-fA-F]{3,4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$")
+);
+impl_string_schema!(
+    Template,
+    "A format string with placeholders, e.g.: `CPU {pct:.1}%`"
+);
+impl_string_schema!(
+    ClickBinding,
+    "A mouse button with optional modifiers, e.g.: `left` or `shift+control+scroll_up`",
+    Some(r"^((mod[1-5]|shift|control)\+)*(left|middle|right|scroll_up|scroll_down)$")
+);
+impl_string_schema!(
+    ItemAction,
+    "A built-in action, e.g.: `paginator:next` or `pulse:mute-toggle sink`",
+    Some(r"^[^:]+:.+$")
+);
+impl_string_schema!(
+    Expression,
+    "Comparisons joined by `and` and `or`, e.g.: `pct < 20` or `state == charging or pct > 90`"
+);
+impl_string_schema!(
+    ThemeColor,
+    "A colour from the theme (bg, fg, dim, red, orange, yellow, green, purple or blue), or a hex colour",
+    Some("^(bg|fg|dim|red|orange|yellow|green|purple|blue|#[0-9a-fA-F]+)$")
+);
+impl_string_schema!(
+    InterfaceFilter,
+    "An interface filter in the format `interface[:type]`, where type is `v4` or `v6`, e.g.: `vpn0:v4` or `:v6`",
+    Some("^[^:]*(:(v4|v6))?$")
+);
+
+// byte sizes can also be given as a number of bytes
+impl JsonSchema for ByteSizeSchema {
+    fn schema_name() -> String {
+        "ByteSize".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(vec![InstanceType::String, InstanceType::Integer].into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some("A size in bytes, e.g.: `1024`, `1 KB` or `1.5 MiB`".into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use figment::providers::{Format, Toml};
+    use figment::Figment;
+    use jsonschema::JSONSchema;
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn sample_config() {
+        let schema = serde_json::to_value(schema()).unwrap();
+        let schema = JSONSchema::compile(&schema).unwrap();
+
+        let config = Figment::from(Toml::file(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("sample_config.toml"),
+        ))
+        .extract::<Value>()
+        .unwrap();
+
+        if let Err(errors) = schema.validate(&config) {
+            let errors = errors
+                .map(|e| format!("{}: {}", e.instance_path, e))
+                .collect::<Vec<_>>();
+            panic!(
+                "sample config doesn't match the schema:\n{}",
+                errors.join("\n")
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_config() {
+        let schema = serde_json::to_value(schema()).unwrap();
+        let schema = JSONSchema::compile(&schema).unwrap();
+
+        let config = serde_json::json!({
+            "items": [
+                { "type": "cpu", "interval": "1s", "thresholds": [{ "value": 10, "color": "pink" }] },
+                { "type": "not-a-real-item" }
+            ]
+        });
+
+        let errors = schema.validate(&config).unwrap_err().collect::<Vec<_>>();
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn interface_filter() {
+        let schema = serde_json::to_value(schema()).unwrap();
+        let schema = JSONSchema::compile(&schema).unwrap();
+
+        // the filter line in the sample config is commented out, so it's checked here
+        let config =
+            |filter: &[&str]| serde_json::json!({ "items": [{ "type": "nic", "filter": filter }] });
+        assert!(schema.is_valid(&config(&["vpn0:v4", ":v6", "wlan0"])));
+        assert!(!schema.is_valid(&config(&["vpn0:ipv4"])));
+        assert!(!schema.is_valid(&config(&[":v5"])));
+
+        // and it agrees with the parser
+        for filter in ["vpn0:v4", ":v6", "wlan0"] {
+            assert!(filter.parse::<InterfaceFilter>().is_ok(), "{}", filter);
+        }
+        assert!("vpn0:ipv4".parse::<InterfaceFilter>().is_err());
+    }
+}
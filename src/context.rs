@@ -1,3 +1,4 @@
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 use std::time::Duration;
 
@@ -7,13 +8,16 @@ use futures::Future;
 use serde_json::Value;
 use sysinfo::{System, SystemExt};
 use tokio::sync::mpsc::error::{SendError, TryRecvError};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::time::sleep;
 
-use crate::config::AppConfig;
+use crate::config::click::ItemAction;
+use crate::config::condition::Condition;
+use crate::config::{AppConfig, Common};
 use crate::i3::bar_item::I3Item;
 use crate::i3::I3ClickEvent;
-use crate::util::RcCell;
+use crate::theme::Theme;
+use crate::util::{ItemFormat, Placeholders, RcCell};
 
 #[derive(Debug)]
 pub enum CustomResponse {
@@ -29,51 +33,239 @@ pub enum BarEvent {
         payload: Vec<String>,
         responder: oneshot::Sender<CustomResponse>,
     },
+    /// A built-in action, triggered by the user's `on_click` configuration
+    Action(ItemAction),
 }
 
 #[derive(Debug)]
 pub struct SharedState {
     pub sys: System,
+    /// Recent log lines from each item (e.g., a script's STDERR), keyed by the item's id
+    logs: HashMap<usize, VecDeque<String>>,
 }
 
 impl SharedState {
+    /// The most log lines kept for each item, older lines are dropped.
+    const MAX_LOG_LINES: usize = 100;
+
     pub fn new() -> RcCell<SharedState> {
         RcCell::new(SharedState {
             // this loads nothing, it's up to each item to load what it needs
             sys: System::new(),
+            logs: HashMap::new(),
+        })
+    }
+
+    /// The log lines recorded by an item, oldest first.
+    pub fn logs(&self, id: usize) -> Vec<String> {
+        self.logs
+            .get(&id)
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn push_log(&mut self, id: usize, line: String) {
+        let lines = self.logs.entry(id).or_default();
+        if lines.len() == Self::MAX_LOG_LINES {
+            lines.pop_front();
+        }
+
+        lines.push_back(line);
+    }
+
+    /// Forget the logs of any items which are no longer running.
+    pub fn retain_logs(&mut self, ids: &[usize]) {
+        self.logs.retain(|id, _| ids.contains(id));
+    }
+}
+
+/// Applies the user's `format`, `format_short`, `hide_when` and `show_when` options to an item's
+/// updates, and sends them to the bar.
+///
+/// This lives as long as the item is in the bar (some items finish after their first update, e.g.:
+/// `raw`), so conditions which don't use the item's values (commands, files, etc) are re-evaluated
+/// on an interval, and the item's last update is sent again when they change.
+#[derive(Debug)]
+pub struct ItemUpdater {
+    tx_item: mpsc::Sender<(I3Item, usize)>,
+    format: ItemFormat,
+    hide_when: Option<Condition>,
+    show_when: Option<Condition>,
+    /// The last results of `hide_when` and `show_when`, if they don't use the item's values
+    hide_when_result: Option<bool>,
+    show_when_result: Option<bool>,
+    /// The item's last update, before the user's options were applied
+    last_update: Option<(I3Item, Placeholders)>,
+    id: usize,
+}
+
+impl ItemUpdater {
+    /// How often conditions which don't use the item's values are re-evaluated.
+    const CONDITION_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(tx_item: mpsc::Sender<(I3Item, usize)>, common: &Common, id: usize) -> RcCell<Self> {
+        RcCell::new(ItemUpdater {
+            tx_item,
+            format: ItemFormat {
+                format: common.format.clone(),
+                format_short: common.format_short.clone(),
+            },
+            hide_when: common.hide_when.clone(),
+            show_when: common.show_when.clone(),
+            hide_when_result: None,
+            show_when_result: None,
+            last_update: None,
+            id,
         })
     }
+
+    async fn update(
+        &mut self,
+        item: I3Item,
+        placeholders: Placeholders,
+    ) -> Result<(), SendError<(I3Item, usize)>> {
+        self.last_update = Some((item.clone(), placeholders.clone()));
+        self.send(item, placeholders).await
+    }
+
+    async fn send(
+        &mut self,
+        item: I3Item,
+        placeholders: Placeholders,
+    ) -> Result<(), SendError<(I3Item, usize)>> {
+        let placeholders = placeholders.item_text(&item);
+        // items may also hide themselves, e.g.: scripts with a special exit code
+        let hidden = item.is_hidden() || self.is_hidden(&placeholders).await;
+        let item = self.format.apply(item, &placeholders).hidden(hidden);
+        self.tx_item.send((item, self.id)).await?;
+        Ok(())
+    }
+
+    /// Check the user's `hide_when` and `show_when` options.
+    async fn is_hidden(&mut self, placeholders: &Placeholders) -> bool {
+        let hide = evaluate(&self.hide_when, &mut self.hide_when_result, placeholders).await;
+        let show = evaluate(&self.show_when, &mut self.show_when_result, placeholders).await;
+        hide == Some(true) || show == Some(false)
+    }
+
+    /// Re-evaluate the conditions which don't use the item's values on an interval, and send the
+    /// item's last update again whenever their results change. This never returns.
+    pub async fn watch_conditions(mut this: RcCell<Self>, mut rx_running: watch::Receiver<bool>) {
+        let independent = |c: &Option<Condition>| c.clone().filter(|c| !c.uses_item());
+        let hide_when = independent(&this.hide_when);
+        let show_when = independent(&this.show_when);
+        if hide_when.is_none() && show_when.is_none() {
+            return std::future::pending().await;
+        }
+
+        let placeholders = Placeholders::new();
+        loop {
+            let mut changed = false;
+            if let Some(condition) = &hide_when {
+                let value = condition.evaluate(&placeholders).await;
+                changed |= this.hide_when_result.replace(value) != Some(value);
+            }
+            if let Some(condition) = &show_when {
+                let value = condition.evaluate(&placeholders).await;
+                changed |= this.show_when_result.replace(value) != Some(value);
+            }
+
+            if changed {
+                if let Some((item, placeholders)) = this.last_update.clone() {
+                    // the bar has gone away
+                    if this.send(item, placeholders).await.is_err() {
+                        return std::future::pending().await;
+                    }
+                }
+            }
+
+            sleep(Self::CONDITION_INTERVAL).await;
+            wait_while_stopped(&mut rx_running).await;
+        }
+    }
+}
+
+/// Evaluate a condition, conditions which don't use the item's values use their last result (and
+/// are only evaluated here if they haven't been yet).
+async fn evaluate(
+    condition: &Option<Condition>,
+    last_result: &mut Option<bool>,
+    placeholders: &Placeholders,
+) -> Option<bool> {
+    let condition = condition.as_ref()?;
+    if condition.uses_item() {
+        return Some(condition.evaluate(placeholders).await);
+    }
+
+    match last_result {
+        Some(result) => Some(*result),
+        None => {
+            let result = condition.evaluate(placeholders).await;
+            Some(*last_result.insert(result))
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Context {
     pub config: RcCell<AppConfig>,
     pub state: RcCell<SharedState>,
-    tx_item: mpsc::Sender<(I3Item, usize)>,
+    updater: RcCell<ItemUpdater>,
     rx_event: mpsc::Receiver<BarEvent>,
-    index: usize,
+    /// Whether the bar is running or stopped (e.g., i3 hides the bar)
+    rx_running: watch::Receiver<bool>,
+    /// The options shared by all items, used for `theme`
+    common: Common,
+    /// A unique id for this item, which doesn't change even if the item's index in the bar does
+    id: usize,
 }
 
 impl Context {
     pub fn new(
         config: RcCell<AppConfig>,
         state: RcCell<SharedState>,
-        tx_item: mpsc::Sender<(I3Item, usize)>,
+        updater: RcCell<ItemUpdater>,
         rx_event: mpsc::Receiver<BarEvent>,
-        index: usize,
+        rx_running: watch::Receiver<bool>,
+        common: Common,
+        id: usize,
     ) -> Context {
         Context {
             config,
             state,
-            tx_item,
+            updater,
             rx_event,
-            index,
+            rx_running,
+            common,
+            id,
+        }
+    }
+
+    /// The theme for this item: the global theme, with any of the item's own overrides applied.
+    pub fn theme(&self) -> Theme {
+        match &self.common.theme {
+            Some(theme) => self.config.theme.with_override(theme),
+            None => self.config.theme.clone(),
         }
     }
 
+    /// Record a log line for this item, these can be read with `istat-ipc get-item-logs`.
+    pub fn log(&mut self, line: impl Into<String>) {
+        self.state.push_log(self.id, line.into());
+    }
+
     pub async fn update_item(&self, item: I3Item) -> Result<(), SendError<(I3Item, usize)>> {
-        self.tx_item.send((item, self.index)).await?;
-        Ok(())
+        self.update_item_with(item, Placeholders::new()).await
+    }
+
+    /// The same as `update_item`, but also provides the item's values, which are used by the user's
+    /// `format` and `format_short` templates, and by `hide_when` and `show_when` expressions.
+    pub async fn update_item_with(
+        &self,
+        item: I3Item,
+        placeholders: Placeholders,
+    ) -> Result<(), SendError<(I3Item, usize)>> {
+        self.updater.clone().update(item, placeholders).await
     }
 
     pub async fn wait_for_event(&mut self, delay: Option<Duration>) -> Option<BarEvent> {
@@ -81,7 +273,10 @@ impl Context {
             None => self.rx_event.recv().await,
             Some(delay) => tokio::select! {
                 event = self.rx_event.recv() => event,
-                _ = sleep(delay) => None
+                _ = sleep(delay) => {
+                    wait_while_stopped(&mut self.rx_running).await;
+                    None
+                }
             },
         }
     }
@@ -101,7 +296,7 @@ impl Context {
                     }
                 }
             }
-            _ = sleep(duration) => {}
+            _ = sleep(duration) => wait_while_stopped(&mut self.rx_running).await,
         }
     }
 
@@ -110,6 +305,16 @@ impl Context {
     }
 }
 
+/// If the bar has been stopped, then wait until it's continued. This is used to pause the timers of
+/// items so they don't needlessly refresh while the bar is hidden.
+async fn wait_while_stopped(rx_running: &mut watch::Receiver<bool>) {
+    while !*rx_running.borrow() {
+        if rx_running.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
 #[derive(Debug, Default, Copy, Clone)]
 pub enum StopAction {
     /// The task finished, and the item will stay in the bar
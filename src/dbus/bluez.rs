@@ -0,0 +1,17 @@
+use zbus::dbus_proxy;
+
+/// The name BlueZ uses on the system bus.
+pub const BLUEZ_SERVICE: &str = "org.bluez";
+
+#[dbus_proxy(
+    default_service = "org.bluez",
+    interface = "org.bluez.Adapter1",
+    gen_blocking = false
+)]
+pub trait Adapter1 {
+    // See: https://git.kernel.org/pub/scm/bluetooth/bluez.git/tree/doc/adapter-api.txt
+    #[dbus_proxy(property)]
+    fn powered(&self) -> zbus::Result<bool>;
+    #[dbus_proxy(property)]
+    fn set_powered(&self, value: bool) -> zbus::Result<()>;
+}
@@ -0,0 +1,12 @@
+use zbus::dbus_proxy;
+
+#[dbus_proxy(
+    default_path = "/org/freedesktop/login1/session/auto",
+    default_service = "org.freedesktop.login1",
+    interface = "org.freedesktop.login1.Session",
+    gen_blocking = false
+)]
+trait Session {
+    // See: https://www.freedesktop.org/software/systemd/man/org.freedesktop.login1.html
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
@@ -1,6 +1,10 @@
+pub mod bluez;
 pub mod dunst;
+pub mod login1;
+pub mod mpris;
 pub mod network_manager;
 pub mod notifications;
+pub mod upower;
 
 use std::error::Error;
 
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use zbus::dbus_proxy;
+use zbus::zvariant::{OwnedValue, Value};
+
+#[dbus_proxy(
+    default_path = "/org/mpris/MediaPlayer2",
+    interface = "org.mpris.MediaPlayer2.Player",
+    gen_blocking = false
+)]
+pub trait MediaPlayer2Player {
+    // See: https://specifications.freedesktop.org/mpris-spec/latest/Player_Interface.html
+    fn play_pause(&self) -> zbus::Result<()>;
+    fn play(&self) -> zbus::Result<()>;
+    fn pause(&self) -> zbus::Result<()>;
+    fn stop(&self) -> zbus::Result<()>;
+    fn next(&self) -> zbus::Result<()>;
+    fn previous(&self) -> zbus::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
+
+/// The prefix of the bus names used by MPRIS players.
+pub const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+/// Read a text field from a player's metadata. Lists (such as `xesam:artist`) are joined together.
+pub fn metadata_text(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    fn text(value: &Value) -> Option<String> {
+        match value {
+            Value::Str(s) => Some(s.to_string()),
+            Value::Array(array) => Some(
+                array
+                    .get()
+                    .iter()
+                    .filter_map(text)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Value::Value(inner) => text(inner),
+            _ => None,
+        }
+    }
+
+    metadata
+        .get(key)
+        .and_then(|value| text(value))
+        .filter(|s| !s.is_empty())
+}
@@ -131,4 +131,34 @@ impl<'a> NotificationsProxy<'a> {
             log::warn!("failed to send notification: {}", e);
         }
     }
+
+    pub async fn battery(&self, pct: impl AsRef<str>, text: impl AsRef<str>, critical: bool) {
+        let mut hints = HashMap::new();
+        hints.insert(
+            "urgency",
+            if critical {
+                Urgency::Critical
+            } else {
+                Urgency::Normal
+            }
+            .into(),
+        );
+
+        if let Err(e) = self
+            .notify_full(
+                Self::APP_NAME,
+                0,
+                "",
+                &format!("Battery {}%", pct.as_ref()),
+                text.as_ref(),
+                &[],
+                hints,
+                // critical notifications stay until they're dismissed
+                if critical { 0 } else { 5_000 },
+            )
+            .await
+        {
+            log::warn!("failed to send notification: {}", e);
+        }
+    }
 }
@@ -0,0 +1,67 @@
+use zbus::dbus_proxy;
+use zbus::zvariant::OwnedObjectPath;
+
+/// The name UPower uses on the system bus.
+pub const UPOWER_SERVICE: &str = "org.freedesktop.UPower";
+
+#[dbus_proxy(
+    default_path = "/org/freedesktop/UPower",
+    default_service = "org.freedesktop.UPower",
+    interface = "org.freedesktop.UPower",
+    gen_blocking = false
+)]
+pub trait UPower {
+    // See: https://upower.freedesktop.org/docs/UPower.html
+    fn enumerate_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    #[dbus_proxy(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.UPower",
+    interface = "org.freedesktop.UPower.Device",
+    gen_blocking = false
+)]
+pub trait Device {
+    // See: https://upower.freedesktop.org/docs/Device.html
+    #[dbus_proxy(property)]
+    fn native_path(&self) -> zbus::Result<String>;
+    #[dbus_proxy(property)]
+    fn model(&self) -> zbus::Result<String>;
+    #[dbus_proxy(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<u32>;
+    #[dbus_proxy(property)]
+    fn power_supply(&self) -> zbus::Result<bool>;
+    #[dbus_proxy(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+    #[dbus_proxy(property)]
+    fn state(&self) -> zbus::Result<u32>;
+    #[dbus_proxy(property)]
+    fn energy(&self) -> zbus::Result<f64>;
+    #[dbus_proxy(property)]
+    fn energy_full(&self) -> zbus::Result<f64>;
+    #[dbus_proxy(property)]
+    fn energy_rate(&self) -> zbus::Result<f64>;
+    #[dbus_proxy(property)]
+    fn time_to_empty(&self) -> zbus::Result<i64>;
+    #[dbus_proxy(property)]
+    fn time_to_full(&self) -> zbus::Result<i64>;
+}
+
+/// Values of a device's `Type` property.
+pub mod device_kind {
+    pub const UNKNOWN: u32 = 0;
+    pub const LINE_POWER: u32 = 1;
+    pub const BATTERY: u32 = 2;
+}
+
+/// Values of a device's `State` property.
+pub mod device_state {
+    pub const CHARGING: u32 = 1;
+    pub const DISCHARGING: u32 = 2;
+    pub const EMPTY: u32 = 3;
+    pub const FULLY_CHARGED: u32 = 4;
+    pub const PENDING_CHARGE: u32 = 5;
+    pub const PENDING_DISCHARGE: u32 = 6;
+}
@@ -3,18 +3,33 @@ use std::error::Error;
 use futures::future::join_all;
 use tokio::sync::mpsc::error::SendError;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::{broadcast, oneshot};
 
-use crate::context::BarEvent;
+use crate::config::click::{find_click_action, ClickAction, ItemAction};
+use crate::config::AppConfig;
+use crate::context::{BarEvent, CustomResponse};
+use crate::ipc::protocol::IpcEvent;
+use crate::util::{exec_with_env, RcCell};
 
 #[derive(Debug, Clone)]
 pub struct Dispatcher {
     inner: Vec<Option<Sender<BarEvent>>>,
+    /// Used to find any click actions configured for items
+    config: RcCell<AppConfig>,
+    /// Used to let IPC subscribers know about click events
+    events: broadcast::Sender<IpcEvent>,
 }
 
 impl Dispatcher {
-    pub fn new(capacity: usize) -> Dispatcher {
+    pub fn new(
+        capacity: usize,
+        config: RcCell<AppConfig>,
+        events: broadcast::Sender<IpcEvent>,
+    ) -> Dispatcher {
         Dispatcher {
             inner: vec![None; capacity],
+            config,
+            events,
         }
     }
 
@@ -26,6 +41,10 @@ impl Dispatcher {
         self.inner[idx] = Some(tx);
     }
 
+    pub fn get(&self, idx: usize) -> Option<&Sender<BarEvent>> {
+        self.inner.get(idx).and_then(Option::as_ref)
+    }
+
     pub async fn signal_all(&self) -> Result<(), Box<dyn Error>> {
         Ok(join_all(
             self.inner
@@ -43,6 +62,67 @@ impl Dispatcher {
     }
 
     pub async fn send_bar_event(&self, idx: usize, ev: BarEvent) -> Result<(), Box<dyn Error>> {
+        let click = match ev {
+            BarEvent::Click(click) => click,
+            ev => return self.send_to_item(idx, ev).await,
+        };
+
+        if !matches!(self.inner.get(idx), Some(Some(_))) {
+            bail!("no item found with index: {}", idx);
+        }
+
+        if self.events.receiver_count() > 0 {
+            let _ = self.events.send(IpcEvent::Click(click.clone()));
+        }
+
+        // the user's click actions take precedence over the item's own click handling
+        let action = self
+            .config
+            .items
+            .get(idx)
+            .and_then(|item| find_click_action(&item.common.on_click, &click))
+            .cloned();
+
+        match action {
+            None => self.send_to_item(idx, BarEvent::Click(click)).await,
+            Some(ClickAction::Command(cmd)) => {
+                // don't wait for the command, so other events aren't held up
+                let env = click.to_env();
+                tokio::task::spawn_local(async move { exec_with_env(cmd, env).await });
+                Ok(())
+            }
+            Some(ClickAction::Action(ItemAction::Custom { args, .. })) => {
+                let (responder, rx) = oneshot::channel();
+                self.send_to_item(
+                    idx,
+                    BarEvent::Custom {
+                        payload: args,
+                        responder,
+                    },
+                )
+                .await?;
+
+                tokio::task::spawn_local(async move {
+                    match rx.await {
+                        Ok(CustomResponse::Json(value)) => {
+                            log::debug!("item[{}] click action response: {}", idx, value)
+                        }
+                        Ok(CustomResponse::Help(help)) => {
+                            log::warn!("item[{}] click action failed: {}", idx, help)
+                        }
+                        Err(_) => log::warn!("item[{}] doesn't support click actions", idx),
+                    }
+                });
+
+                Ok(())
+            }
+            Some(ClickAction::Action(action)) => {
+                self.send_to_item(idx, BarEvent::Action(action)).await
+            }
+        }
+    }
+
+    async fn send_to_item(&self, idx: usize, ev: BarEvent) -> Result<(), Box<dyn Error>> {
         match self.inner.get(idx) {
             Some(Some(tx)) => {
                 // if the channel fills up (the bar never reads click events), since this is a bounded channel
@@ -61,6 +141,7 @@ impl Dispatcher {
                         idx
                     );
                 }
+
                 Ok(())
             }
             None | Some(None) => bail!("no item found with index: {}", idx),
@@ -3,13 +3,15 @@ use std::error::Error;
 
 use async_trait::async_trait;
 use hex_color::HexColor;
+use schemars::JsonSchema;
 use serde::Serialize;
 use serde_derive::Deserialize;
 use serde_json::Value;
 
+use crate::config::schema::HexColorSchema;
 use crate::context::{BarItem, Context, StopAction};
 
-#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum I3Align {
     #[default]
@@ -18,7 +20,7 @@ pub enum I3Align {
     Left,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum I3Markup {
     #[default]
@@ -35,7 +37,7 @@ impl I3Markup {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
 #[serde(untagged, rename_all = "lowercase")]
 pub enum I3MinWidth {
     Pixels(usize),
@@ -56,7 +58,7 @@ impl Serialize for I3MinWidth {
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
 pub struct I3Item {
     pub full_text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -68,10 +70,13 @@ pub struct I3Item {
     name: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<HexColorSchema>")]
     color: Option<HexColor>,
     #[serde(rename = "background", skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<HexColorSchema>")]
     background_color: Option<HexColor>,
     #[serde(rename = "border", skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<HexColorSchema>")]
     border_color: Option<HexColor>,
 
     #[serde(rename = "border_top", skip_serializing_if = "Option::is_none")]
@@ -103,6 +108,11 @@ pub struct I3Item {
 
     #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
     additional_data: HashMap<String, Value>,
+
+    /// Set when the user's `hide_when` or `show_when` options hide this item. Hidden items aren't
+    /// sent to i3, so this isn't part of the protocol.
+    #[serde(skip)]
+    hidden: bool,
 }
 
 macro_rules! impl_get_set {
@@ -168,6 +178,7 @@ impl I3Item {
             separator_block_width_px: None,
             markup: None,
             additional_data: HashMap::new(),
+            hidden: false,
         }
     }
 
@@ -188,6 +199,15 @@ impl I3Item {
         self
     }
 
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
     impl_get_set! (
         /// Set the name of the item. NOTE: setting this from within an item implementation will
         /// have no effect, since istat manages this property itself from config.
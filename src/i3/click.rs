@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use serde_derive::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
-#[derive(Debug, Default, Copy, Clone, Serialize_repr, Deserialize_repr, PartialEq, Eq)]
+#[derive(Debug, Default, Copy, Clone, Serialize_repr, Deserialize_repr, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum I3Button {
     #[default]
@@ -12,7 +14,7 @@ pub enum I3Button {
     ScrollDown = 5,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum I3Modifier {
     Mod1,
     Mod2,
@@ -39,3 +41,33 @@ pub struct I3ClickEvent {
     pub width: usize,
     pub height: usize,
 }
+
+impl I3ClickEvent {
+    /// The environment variables set for commands run in response to this click event.
+    pub fn to_env(&self) -> HashMap<&'static str, String> {
+        let mut env = HashMap::new();
+        if let Some(name) = &self.name {
+            env.insert("I3_NAME", name.to_string());
+        }
+        env.insert(
+            "I3_MODIFIERS",
+            self.modifiers
+                .iter()
+                // SAFETY: if these types don't serialise then things would have gone wrong previously
+                .map(|m| serde_json::to_string(m).unwrap())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        // SAFETY: if these types don't serialise then things would have gone wrong previously
+        env.insert("I3_BUTTON", serde_json::to_string(&self.button).unwrap());
+        env.insert("I3_X", self.x.to_string());
+        env.insert("I3_Y", self.y.to_string());
+        env.insert("I3_RELATIVE_X", self.relative_x.to_string());
+        env.insert("I3_RELATIVE_Y", self.relative_y.to_string());
+        env.insert("I3_OUTPUT_X", self.output_x.to_string());
+        env.insert("I3_OUTPUT_Y", self.output_y.to_string());
+        env.insert("I3_WIDTH", self.width.to_string());
+        env.insert("I3_HEIGHT", self.height.to_string());
+        env
+    }
+}
@@ -1,3 +1,4 @@
+use libc::{SIGCONT, SIGTSTP};
 use serde_derive::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -15,8 +16,11 @@ impl Default for I3BarHeader {
     fn default() -> Self {
         I3BarHeader {
             version: 1,
-            stop_signal: None,
-            cont_signal: None,
+            // NOTE: i3 sends these signals to the whole process group, so any child processes will
+            // still be stopped and continued just like they would be with the default signals, but
+            // this way we're able to handle them ourselves and stop refreshing items while hidden
+            stop_signal: Some(SIGTSTP as usize),
+            cont_signal: Some(SIGCONT as usize),
             click_events: Some(true),
         }
     }
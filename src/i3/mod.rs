@@ -2,6 +2,7 @@ pub mod bar_item;
 pub mod click;
 pub mod header;
 pub mod ipc;
+pub mod wm;
 
 pub use bar_item::*;
 pub use click::*;
@@ -0,0 +1,421 @@
+//! A client for the window manager's IPC (i3's, or sway's which is compatible with it and extends it).
+//! See: https://i3wm.org/docs/ipc.html
+
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::sync::mpsc::{self, Receiver};
+
+const MAGIC: &[u8] = b"i3-ipc";
+/// Magic string, payload length and message type
+const HEADER_LEN: usize = MAGIC.len() + 4 + 4;
+/// The highest bit of the message type is set for events
+const EVENT_BIT: u32 = 1 << 31;
+
+const MSG_RUN_COMMAND: u32 = 0;
+const MSG_SUBSCRIBE: u32 = 2;
+const MSG_GET_TREE: u32 = 4;
+const MSG_GET_BINDING_STATE: u32 = 12;
+/// Only supported by sway
+const MSG_GET_INPUTS: u32 = 100;
+
+const EVENT_WORKSPACE: u32 = EVENT_BIT;
+const EVENT_MODE: u32 = EVENT_BIT | 2;
+const EVENT_WINDOW: u32 = EVENT_BIT | 3;
+/// Only supported by sway
+const EVENT_INPUT: u32 = EVENT_BIT | 0x15;
+
+/// The name of the workspace which holds the windows in the scratchpad.
+pub const SCRATCHPAD_WORKSPACE: &str = "__i3_scratch";
+
+/// The window manager's socket, sway sets `SWAYSOCK` rather than `I3SOCK`.
+pub fn wm_socket_path() -> Result<PathBuf, Box<dyn Error>> {
+    match env::var_os("I3SOCK").or_else(|| env::var_os("SWAYSOCK")) {
+        Some(path) => Ok(path.into()),
+        None => bail!("neither I3SOCK nor SWAYSOCK are set"),
+    }
+}
+
+/// Whether the window manager is sway, rather than i3.
+pub fn is_sway() -> bool {
+    env::var_os("SWAYSOCK").is_some()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum I3EventType {
+    Workspace,
+    Mode,
+    Window,
+    /// Only supported by sway
+    Input,
+}
+
+impl I3EventType {
+    fn name(&self) -> &'static str {
+        match self {
+            I3EventType::Workspace => "workspace",
+            I3EventType::Mode => "mode",
+            I3EventType::Window => "window",
+            I3EventType::Input => "input",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkspaceEvent {
+    pub change: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModeEvent {
+    /// The name of the mode, `default` if no mode is active
+    pub change: String,
+    #[serde(default)]
+    pub pango_markup: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WindowEvent {
+    pub change: String,
+    pub container: I3Node,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InputEvent {
+    pub change: String,
+    pub input: I3Input,
+}
+
+#[derive(Debug, Clone)]
+pub enum I3Event {
+    Workspace(WorkspaceEvent),
+    Mode(ModeEvent),
+    Window(WindowEvent),
+    Input(InputEvent),
+}
+
+/// An input device, only supported by sway.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct I3Input {
+    pub identifier: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub xkb_layout_names: Vec<String>,
+    pub xkb_active_layout_index: Option<usize>,
+}
+
+/// A node in the window manager's layout tree.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct I3Node {
+    pub id: i64,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub focused: bool,
+    /// The X11 window id, only set for windows on i3 or XWayland windows on sway
+    pub window: Option<u32>,
+    /// Only set for native wayland windows on sway
+    pub app_id: Option<String>,
+    pub window_properties: Option<WindowProperties>,
+    pub nodes: Vec<I3Node>,
+    pub floating_nodes: Vec<I3Node>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct WindowProperties {
+    pub class: Option<String>,
+    pub instance: Option<String>,
+}
+
+impl I3Node {
+    /// Whether this node is a window (as opposed to a container, workspace, output, etc).
+    pub fn is_window(&self) -> bool {
+        self.window.is_some() || self.app_id.is_some()
+    }
+
+    /// The window's class on X11, or its app id on wayland.
+    pub fn app(&self) -> Option<&str> {
+        self.app_id.as_deref().or_else(|| {
+            self.window_properties
+                .as_ref()
+                .and_then(|props| props.class.as_deref())
+        })
+    }
+
+    fn children(&self) -> impl Iterator<Item = &I3Node> {
+        self.nodes.iter().chain(self.floating_nodes.iter())
+    }
+
+    /// Find the focused node, if any.
+    pub fn find_focused(&self) -> Option<&I3Node> {
+        if self.focused {
+            return Some(self);
+        }
+
+        self.children().find_map(|node| node.find_focused())
+    }
+
+    /// Find the first node matching the predicate, searching depth first.
+    pub fn find(&self, predicate: &impl Fn(&I3Node) -> bool) -> Option<&I3Node> {
+        if predicate(self) {
+            return Some(self);
+        }
+
+        self.children().find_map(|node| node.find(predicate))
+    }
+
+    /// The number of windows in this node.
+    pub fn window_count(&self) -> usize {
+        if self.is_window() {
+            return 1;
+        }
+
+        self.children().map(|node| node.window_count()).sum()
+    }
+}
+
+#[derive(Debug)]
+pub struct I3Ipc {
+    stream: UnixStream,
+}
+
+impl I3Ipc {
+    pub async fn connect() -> Result<I3Ipc, Box<dyn Error>> {
+        let path = wm_socket_path()?;
+        let stream = match UnixStream::connect(&path).await {
+            Ok(stream) => stream,
+            Err(e) => bail!("failed to connect to {}: {}", path.display(), e),
+        };
+
+        Ok(I3Ipc { stream })
+    }
+
+    async fn send(&mut self, msg_type: u32, payload: &[u8]) -> Result<(), Box<dyn Error>> {
+        let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        buf.extend_from_slice(&msg_type.to_ne_bytes());
+        buf.extend_from_slice(payload);
+        self.stream.write_all(&buf).await?;
+
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> Result<(u32, Vec<u8>), Box<dyn Error>> {
+        let mut header = [0; HEADER_LEN];
+        self.stream.read_exact(&mut header).await?;
+        if &header[..MAGIC.len()] != MAGIC {
+            bail!("invalid message from window manager: {:?}", header);
+        }
+
+        let (len, msg_type) = parse_header(&header);
+        let mut payload = vec![0; len as usize];
+        self.stream.read_exact(&mut payload).await?;
+
+        Ok((msg_type, payload))
+    }
+
+    /// Send a message and wait for its reply. Any events received in the meantime are dropped, so
+    /// use separate connections for requests and subscriptions.
+    async fn request<T: DeserializeOwned>(
+        &mut self,
+        msg_type: u32,
+        payload: &[u8],
+    ) -> Result<T, Box<dyn Error>> {
+        self.send(msg_type, payload).await?;
+        loop {
+            let (reply_type, payload) = self.recv().await?;
+            if reply_type == msg_type {
+                return Ok(serde_json::from_slice(&payload)?);
+            }
+        }
+    }
+
+    /// Run a command, e.g.: `scratchpad show`.
+    pub async fn run_command(&mut self, command: &str) -> Result<(), Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct CommandReply {
+            success: bool,
+            error: Option<String>,
+        }
+
+        let replies = self
+            .request::<Vec<CommandReply>>(MSG_RUN_COMMAND, command.as_bytes())
+            .await?;
+        for reply in replies {
+            if !reply.success {
+                bail!(
+                    "failed to run command: {}: {}",
+                    command,
+                    reply.error.unwrap_or_default()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_tree(&mut self) -> Result<I3Node, Box<dyn Error>> {
+        self.request(MSG_GET_TREE, &[]).await
+    }
+
+    /// The name of the current binding mode.
+    pub async fn get_binding_state(&mut self) -> Result<String, Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct BindingState {
+            name: String,
+        }
+
+        let state = self
+            .request::<BindingState>(MSG_GET_BINDING_STATE, &[])
+            .await?;
+        Ok(state.name)
+    }
+
+    /// The input devices, only supported by sway.
+    pub async fn get_inputs(&mut self) -> Result<Vec<I3Input>, Box<dyn Error>> {
+        self.request(MSG_GET_INPUTS, &[]).await
+    }
+
+    /// Subscribe to the given events, a message is sent for each one received. This consumes the
+    /// connection, since replies to other requests can't be received on it anymore.
+    pub async fn subscribe(
+        mut self,
+        events: &[I3EventType],
+    ) -> Result<Receiver<I3Event>, Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct SubscribeReply {
+            success: bool,
+        }
+
+        let names = events.iter().map(|e| e.name()).collect::<Vec<_>>();
+        let payload = serde_json::to_vec(&json!(names))?;
+        let reply = self
+            .request::<SubscribeReply>(MSG_SUBSCRIBE, &payload)
+            .await?;
+        if !reply.success {
+            bail!("failed to subscribe to events: {:?}", names);
+        }
+
+        // reading isn't cancel safe, so it's done in its own task rather than in `select!`s
+        let (tx, rx) = mpsc::channel(32);
+        tokio::task::spawn_local(async move {
+            let err = loop {
+                let event = match self.next_event().await {
+                    Ok(event) => event,
+                    Err(e) => break e,
+                };
+
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            };
+
+            log::error!("unexpected failure of window manager event stream: {}", err);
+        });
+
+        Ok(rx)
+    }
+
+    async fn next_event(&mut self) -> Result<I3Event, Box<dyn Error>> {
+        loop {
+            let (msg_type, payload) = self.recv().await?;
+            let event = match msg_type {
+                EVENT_WORKSPACE => I3Event::Workspace(serde_json::from_slice(&payload)?),
+                EVENT_MODE => I3Event::Mode(serde_json::from_slice(&payload)?),
+                EVENT_WINDOW => I3Event::Window(serde_json::from_slice(&payload)?),
+                EVENT_INPUT => I3Event::Input(serde_json::from_slice(&payload)?),
+                // other events, or replies to other messages
+                _ => continue,
+            };
+
+            return Ok(event);
+        }
+    }
+}
+
+fn parse_header(header: &[u8; HEADER_LEN]) -> (u32, u32) {
+    let n = |i: usize| u32::from_ne_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+    (n(MAGIC.len()), n(MAGIC.len() + 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header() {
+        let mut header = [0; HEADER_LEN];
+        header[..MAGIC.len()].copy_from_slice(MAGIC);
+        header[6..10].copy_from_slice(&42u32.to_ne_bytes());
+        header[10..].copy_from_slice(&EVENT_MODE.to_ne_bytes());
+        assert_eq!(parse_header(&header), (42, EVENT_MODE));
+    }
+
+    #[test]
+    fn tree() {
+        let tree = serde_json::from_value::<I3Node>(json!({
+            "id": 1,
+            "type": "root",
+            "nodes": [
+                {
+                    "id": 2,
+                    "type": "output",
+                    "name": "__i3",
+                    "nodes": [{
+                        "id": 3,
+                        "type": "workspace",
+                        "name": "__i3_scratch",
+                        "floating_nodes": [
+                            { "id": 4, "type": "floating_con", "nodes": [
+                                { "id": 5, "type": "con", "name": "a", "window": 100 }
+                            ] },
+                            { "id": 6, "type": "floating_con", "nodes": [
+                                { "id": 7, "type": "con", "name": "b", "app_id": "foot" }
+                            ] }
+                        ]
+                    }]
+                },
+                {
+                    "id": 8,
+                    "type": "output",
+                    "name": "eDP-1",
+                    "nodes": [{
+                        "id": 9,
+                        "type": "workspace",
+                        "name": "1",
+                        "nodes": [{
+                            "id": 10,
+                            "type": "con",
+                            "name": "editor",
+                            "focused": true,
+                            "window": 101,
+                            "window_properties": { "class": "Code" }
+                        }]
+                    }]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let focused = tree.find_focused().unwrap();
+        assert_eq!(focused.id, 10);
+        assert_eq!(focused.name.as_deref(), Some("editor"));
+        assert_eq!(focused.app(), Some("Code"));
+
+        let scratchpad = tree
+            .find(&|node| node.name.as_deref() == Some(SCRATCHPAD_WORKSPACE))
+            .unwrap();
+        assert_eq!(scratchpad.window_count(), 2);
+        assert_eq!(tree.window_count(), 3);
+    }
+}
@@ -2,10 +2,19 @@ use std::error::Error;
 use std::io::ErrorKind;
 
 use tokio::net::UnixStream;
-use tokio::sync::oneshot;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, oneshot};
 
+use crate::config::{AppConfig, Item};
 use crate::context::{BarEvent, CustomResponse};
-use crate::ipc::protocol::{IpcBarEvent, IpcMessage, IpcReply, IpcResult, IPC_HEADER_LEN};
+use crate::ipc::protocol::{
+    IpcBarEvent,
+    IpcEvent,
+    IpcMessage,
+    IpcReply,
+    IpcResult,
+    IPC_HEADER_LEN,
+};
 use crate::ipc::server::send_ipc_response;
 use crate::ipc::IpcContext;
 use crate::theme::Theme;
@@ -76,7 +85,14 @@ async fn handle_ipc_request(
             ctx.token.cancel();
         }
         IpcMessage::GetBar => {
-            send_ipc_response(&stream, &IpcReply::Value(serde_json::to_value(&*ctx.bar)?)).await?;
+            let bar = ctx.bar.items();
+            send_ipc_response(&stream, &IpcReply::Value(serde_json::to_value(&*bar)?)).await?;
+        }
+        IpcMessage::Subscribe => {
+            let rx = ctx.bar.subscribe();
+            // let the client know it's subscribed, so it knows it won't miss any events from now on
+            send_ipc_response(&stream, &IpcReply::Result(IpcResult::Success(None))).await?;
+            handle_ipc_subscription(&stream, rx).await?;
         }
         IpcMessage::Info => {
             let info = serde_json::to_value(ctx.config.item_idx_to_name())?;
@@ -100,48 +116,80 @@ async fn handle_ipc_request(
             let reply = match serde_json::from_value::<Theme>(json) {
                 Ok(new) => {
                     ctx.config.theme = new;
+                    ctx.bar
+                        .emit(IpcEvent::Theme(serde_json::to_value(&ctx.config.theme)?));
                     IpcReply::Result(IpcResult::Success(None))
                 }
                 Err(e) => IpcReply::Result(IpcResult::Failure(e.to_string())),
             };
             send_ipc_response(&stream, &reply).await?;
         }
+        IpcMessage::Reload => {
+            let reply = match ctx.bar.reload().await {
+                Ok(()) => IpcReply::Result(IpcResult::Success(None)),
+                Err(e) => {
+                    log::error!("failed to reload config: {}", e);
+                    IpcReply::Result(IpcResult::Failure(e.to_string()))
+                }
+            };
+            send_ipc_response(&stream, &reply).await?;
+        }
         IpcMessage::RefreshAll => {
             ctx.dispatcher.signal_all().await?;
             send_ipc_response(&stream, &IpcReply::Result(IpcResult::Success(None))).await?;
         }
+        IpcMessage::AddItem { index, item } => {
+            let reply = match serde_json::from_value::<Item>(item) {
+                Ok(item) => match ctx.bar.insert_item(index, item) {
+                    Ok(idx) => IpcReply::Result(IpcResult::Success(Some(idx.to_string()))),
+                    Err(e) => IpcReply::Result(IpcResult::Failure(e.to_string())),
+                },
+                Err(e) => IpcReply::Result(IpcResult::Failure(e.to_string())),
+            };
+            send_ipc_response(&stream, &reply).await?;
+        }
+        IpcMessage::RemoveItem { target } => {
+            let reply = match find_item_index(&ctx.config, &target) {
+                Ok(idx) => match ctx.bar.remove_item(idx) {
+                    Ok(()) => IpcReply::Result(IpcResult::Success(None)),
+                    Err(e) => IpcReply::Result(IpcResult::Failure(e.to_string())),
+                },
+                Err(e) => IpcReply::Result(IpcResult::Failure(e)),
+            };
+            send_ipc_response(&stream, &reply).await?;
+        }
+        IpcMessage::ReplaceItem { target, item } => {
+            let reply = match (
+                find_item_index(&ctx.config, &target),
+                serde_json::from_value::<Item>(item),
+            ) {
+                (Ok(idx), Ok(item)) => match ctx.bar.replace_item(idx, item) {
+                    Ok(()) => IpcReply::Result(IpcResult::Success(None)),
+                    Err(e) => IpcReply::Result(IpcResult::Failure(e.to_string())),
+                },
+                (Err(e), _) => IpcReply::Result(IpcResult::Failure(e)),
+                (_, Err(e)) => IpcReply::Result(IpcResult::Failure(e.to_string())),
+            };
+            send_ipc_response(&stream, &reply).await?;
+        }
+        IpcMessage::GetItemLogs { target } => {
+            let reply = match find_item_index(&ctx.config, &target) {
+                Ok(idx) => match ctx.bar.item_logs(idx) {
+                    Ok(logs) => IpcReply::Value(serde_json::to_value(logs)?),
+                    Err(e) => IpcReply::Result(IpcResult::Failure(e.to_string())),
+                },
+                Err(e) => IpcReply::Result(IpcResult::Failure(e)),
+            };
+            send_ipc_response(&stream, &reply).await?;
+        }
         IpcMessage::BarEvent { instance, event } => {
-            // NOTE: special considerations here for `instance`: if it's a number, then it maps to the item at the index
-            // otherwise, it's interpreted as a name and the first item with that name is chosen
-            let instance = match instance.parse::<usize>() {
-                // ipc message contained an index
+            let instance = match find_item_index(&ctx.config, &instance) {
                 Ok(idx) => idx,
-                Err(e) => {
-                    match ctx
-                        .config
-                        .item_idx_to_name()
-                        .iter()
-                        .find_map(
-                            |(idx, name)| {
-                                if instance == *name {
-                                    Some(idx)
-                                } else {
-                                    None
-                                }
-                            },
-                        ) {
-                        // ipc message contained a tag
-                        Some(idx) => *idx,
-                        // error
-                        None => {
-                            let err = format!("failed to parse ipc instance property: {}", e);
-                            log::warn!("{}", err);
-                            send_ipc_response(&stream, &IpcReply::Result(IpcResult::Failure(err)))
-                                .await?;
+                Err(err) => {
+                    log::warn!("{}", err);
+                    send_ipc_response(&stream, &IpcReply::Result(IpcResult::Failure(err))).await?;
 
-                            return Ok(());
-                        }
-                    }
+                    return Ok(());
                 }
             };
 
@@ -176,3 +224,51 @@ async fn handle_ipc_request(
 
     Ok(())
 }
+
+/// Find the index of the item referenced in an IPC message.
+// NOTE: special considerations here for `target`: if it's a number, then it maps to the item at the index
+// otherwise, it's interpreted as a name and the first item with that name is chosen
+fn find_item_index(config: &AppConfig, target: &str) -> Result<usize, String> {
+    match target.parse::<usize>() {
+        // ipc message contained an index
+        Ok(idx) => Ok(idx),
+        Err(e) => config
+            .item_idx_to_name()
+            .iter()
+            .find_map(|(idx, name)| if target == *name { Some(*idx) } else { None })
+            // ipc message contained a name
+            .ok_or_else(|| format!("failed to parse ipc instance property: {}", e)),
+    }
+}
+
+/// Send events to the client as they happen, until the client disconnects.
+async fn handle_ipc_subscription(
+    stream: &UnixStream,
+    mut rx: broadcast::Receiver<IpcEvent>,
+) -> Result<(), Box<dyn Error>> {
+    let mut buf = [0; 1];
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) => send_ipc_response(stream, &event).await?,
+                Err(RecvError::Lagged(n)) => {
+                    log::warn!("ipc subscriber fell behind, skipped {} events", n)
+                }
+                Err(RecvError::Closed) => break,
+            },
+            // clients don't send anything after subscribing, so this is only ready when they disconnect
+            result = stream.readable() => {
+                result?;
+                match stream.try_read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(_) => continue,
+                    // there may be false positives readiness events
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => continue,
+                    Err(e) => bail!(e),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
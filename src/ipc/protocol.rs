@@ -21,7 +21,10 @@ pub enum IpcBarEvent {
 pub enum IpcMessage {
     Info,
     RefreshAll,
+    Reload,
     GetBar,
+    /// Keep the connection open, and receive an `IpcEvent` each time something happens
+    Subscribe,
     GetConfig,
     GetTheme,
     SetTheme(Value),
@@ -29,6 +32,24 @@ pub enum IpcMessage {
         instance: String,
         event: IpcBarEvent,
     },
+    /// Insert a new item into the bar, if no index is given then it's added to the end
+    AddItem {
+        index: Option<usize>,
+        item: Value,
+    },
+    /// Remove an item from the bar, `target` is either an index or the name of the item
+    RemoveItem {
+        target: String,
+    },
+    /// Replace the configuration of an item, `target` is either an index or the name of the item
+    ReplaceItem {
+        target: String,
+        item: Value,
+    },
+    /// Get the recent log lines of an item, `target` is either an index or the name of the item
+    GetItemLogs {
+        target: String,
+    },
     Shutdown,
 }
 
@@ -48,6 +69,26 @@ pub enum IpcResult {
     Failure(String),
 }
 
+/// Events sent to clients which have subscribed with `IpcMessage::Subscribe`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "detail")]
+pub enum IpcEvent {
+    /// The bar was updated, this is the same JSON which is sent to i3
+    Bar(Value),
+    /// A click event was sent to an item
+    Click(I3ClickEvent),
+    /// An item exited with an error
+    Error {
+        index: usize,
+        name: String,
+        message: String,
+    },
+    /// An item requested to be restarted
+    Restart { index: usize, name: String },
+    /// The theme was changed
+    Theme(Value),
+}
+
 pub fn encode_ipc_msg<T: Serialize>(t: T) -> Result<Vec<u8>, Box<dyn Error>> {
     let msg = serde_json::to_vec(&t)?;
     // header is a u64 of length
@@ -78,4 +119,20 @@ mod tests {
         assert_eq!(header, 43_u64.to_le_bytes());
         assert_eq!(body, br#"{"result":{"type":"success","detail":null}}"#);
     }
+
+    #[test]
+    fn test_encode_ipc_event() {
+        let event = IpcEvent::Restart {
+            index: 1,
+            name: "script".into(),
+        };
+        let bytes = encode_ipc_msg::<IpcEvent>(event).unwrap();
+        let header = &bytes[0..IPC_HEADER_LEN];
+        let body = &bytes[IPC_HEADER_LEN..];
+        assert_eq!(header, 55_u64.to_le_bytes());
+        assert_eq!(
+            body,
+            br#"{"type":"restart","detail":{"index":1,"name":"script"}}"#
+        );
+    }
 }
@@ -2,11 +2,12 @@ use std::convert::Infallible;
 use std::error::Error;
 use std::io::ErrorKind;
 
+use serde::Serialize;
 use tokio::net::{UnixListener, UnixStream};
 
 use super::client::handle_ipc_client;
 use crate::config::AppConfig;
-use crate::ipc::protocol::{encode_ipc_msg, IpcReply};
+use crate::ipc::protocol::encode_ipc_msg;
 use crate::ipc::IpcContext;
 use crate::util::RcCell;
 
@@ -43,7 +44,10 @@ pub async fn handle_ipc_events(
     }
 }
 
-pub async fn send_ipc_response(stream: &UnixStream, resp: &IpcReply) -> Result<(), Box<dyn Error>> {
+pub async fn send_ipc_response<T: Serialize>(
+    stream: &UnixStream,
+    resp: &T,
+) -> Result<(), Box<dyn Error>> {
     let data = encode_ipc_msg(resp)?;
     let mut idx = 0;
     loop {
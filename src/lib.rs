@@ -3,6 +3,7 @@
 #[macro_use]
 pub mod macros;
 
+pub mod bar;
 pub mod bar_items;
 pub mod cli;
 pub mod config;
@@ -12,6 +13,7 @@ pub mod dispatcher;
 pub mod human_time;
 pub mod i3;
 pub mod ipc;
+pub mod output;
 pub mod signals;
 pub mod theme;
 pub mod util;
@@ -0,0 +1,29 @@
+use std::error::Error;
+
+use super::Output;
+use crate::i3::header::I3BarHeader;
+use crate::i3::I3Item;
+
+/// The i3bar protocol, see: https://i3wm.org/docs/i3bar-protocol.html
+#[derive(Debug)]
+pub struct I3Bar;
+
+impl Output for I3Bar {
+    fn header(&self) -> Result<Option<String>, Box<dyn Error>> {
+        // the header, and the opening bracket for the "infinite array"
+        Ok(Some(format!(
+            "{}\n[",
+            serde_json::to_string(&I3BarHeader::default())?
+        )))
+    }
+
+    fn format(&self, bar: &[I3Item]) -> String {
+        match serde_json::to_string(&bar) {
+            Ok(json) => format!("{},", json),
+            Err(e) => {
+                log::error!("failed to serialise bar to json: {}", e);
+                r#"[{"full_text":"FATAL ERROR: see logs in stderr","color":"black","background":"red"}],"#.into()
+            }
+        }
+    }
+}
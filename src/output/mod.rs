@@ -0,0 +1,113 @@
+//! The bar can be printed in formats for different status bars, see `OutputKind`.
+
+mod i3bar;
+mod pango;
+mod plain;
+mod polybar;
+mod waybar;
+
+use std::error::Error;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use clap::ValueEnum;
+use hex_color::HexColor;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+pub use self::i3bar::I3Bar;
+use self::pango::Span;
+pub use self::plain::Plain;
+pub use self::polybar::Polybar;
+pub use self::waybar::Waybar;
+use crate::i3::{I3Item, I3Markup};
+
+/// Formats the bar for a status bar, each time the bar changes it's printed as a line to STDOUT.
+pub trait Output: Debug {
+    /// Printed once when the bar starts, before the bar is printed for the first time.
+    fn header(&self) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(None)
+    }
+
+    /// Format the bar as a line. Hidden items have already been removed.
+    fn format(&self, bar: &[I3Item]) -> String;
+}
+
+/// Which status bar the output is for.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputKind {
+    /// The i3bar protocol, used by i3bar and swaybar (the default)
+    #[default]
+    I3bar,
+    /// JSON for a waybar custom module with `return-type` set to `json`
+    Waybar,
+    /// Text with polybar's formatting tags, for a script module with `tail` set to `true`
+    Polybar,
+    /// Text with lemonbar's formatting tags
+    Lemonbar,
+    /// Text without any formatting, e.g.: for terminals or tmux
+    Plain,
+}
+
+impl OutputKind {
+    /// Create the output, the socket is used for outputs which send click events via `istat-ipc`.
+    pub fn create(&self, socket: PathBuf) -> Rc<dyn Output> {
+        match self {
+            OutputKind::I3bar => Rc::new(I3Bar),
+            OutputKind::Waybar => Rc::new(Waybar),
+            OutputKind::Polybar | OutputKind::Lemonbar => Rc::new(Polybar::new(socket)),
+            OutputKind::Plain => Rc::new(Plain),
+        }
+    }
+
+    /// Whether click events are read from STDIN, only i3bar (and swaybar) send them.
+    pub fn click_events(&self) -> bool {
+        matches!(self, OutputKind::I3bar)
+    }
+}
+
+/// The items which are shown, since some items (e.g., `dunst`) have no text when they've nothing to show.
+fn visible(bar: &[I3Item]) -> impl Iterator<Item = &I3Item> {
+    bar.iter().filter(|item| !item.full_text.is_empty())
+}
+
+/// The text placed after an item, for outputs which join all items into a single line.
+fn separator(item: &I3Item) -> &'static str {
+    match (item.get_separator(), item.get_separator_block_width_px()) {
+        (Some(false), Some(0)) => "",
+        (Some(false), _) => " ",
+        _ => " | ",
+    }
+}
+
+/// The item's text and colours. Pango markup is only parsed if the item uses it, and the item's own
+/// colours are used for any text without colours of its own.
+fn spans(item: &I3Item) -> Vec<Span> {
+    let mut spans = match item.get_markup() {
+        Some(I3Markup::Pango) => pango::parse(&item.full_text),
+        _ => vec![Span {
+            text: item.full_text.clone(),
+            fg: None,
+            bg: None,
+        }],
+    };
+
+    for span in &mut spans {
+        span.fg = span.fg.or(item.get_color().copied());
+        span.bg = span.bg.or(item.get_background_color().copied());
+    }
+
+    spans
+}
+
+/// Format a colour as `#RRGGBB`, or `#AARRGGBB` if it's transparent (the format used by polybar and lemonbar).
+fn argb(color: &HexColor) -> String {
+    match color.a {
+        u8::MAX => format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b),
+        a => format!("#{:02x}{:02x}{:02x}{:02x}", a, color.r, color.g, color.b),
+    }
+}
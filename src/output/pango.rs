@@ -0,0 +1,176 @@
+use hex_color::HexColor;
+
+/// A run of text from pango markup, along with the colours which apply to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub fg: Option<HexColor>,
+    pub bg: Option<HexColor>,
+}
+
+/// Parse pango markup into runs of text. Only colours are kept, all other attributes (e.g., `<b>` or
+/// `size`) are dropped since other bars can't display them. Colours which aren't hex colours are ignored.
+pub fn parse(markup: &str) -> Vec<Span> {
+    // each open tag's colours, inner tags inherit the colours of outer tags
+    let mut stack: Vec<(Option<HexColor>, Option<HexColor>)> = vec![];
+    let mut spans: Vec<Span> = vec![];
+    let mut text = String::new();
+
+    let flush = |text: &mut String,
+                 spans: &mut Vec<Span>,
+                 stack: &[(Option<HexColor>, Option<HexColor>)]| {
+        if text.is_empty() {
+            return;
+        }
+
+        let fg = stack.iter().rev().find_map(|(fg, _)| *fg);
+        let bg = stack.iter().rev().find_map(|(_, bg)| *bg);
+        let text = unescape(&std::mem::take(text));
+        match spans.last_mut() {
+            Some(last) if last.fg == fg && last.bg == bg => last.text.push_str(&text),
+            _ => spans.push(Span { text, fg, bg }),
+        }
+    };
+
+    let mut chars = markup.chars();
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            text.push(c);
+            continue;
+        }
+
+        flush(&mut text, &mut spans, &stack);
+
+        let tag = chars.by_ref().take_while(|c| *c != '>').collect::<String>();
+        if tag.starts_with('/') {
+            stack.pop();
+        } else {
+            let attr = |names: &[&str]| {
+                attributes(&tag)
+                    .into_iter()
+                    .find(|(name, _)| names.contains(name))
+                    .and_then(|(_, value)| HexColor::parse(value).ok())
+            };
+
+            stack.push((
+                attr(&["foreground", "fgcolor", "color"]),
+                attr(&["background", "bgcolor"]),
+            ));
+        }
+    }
+
+    flush(&mut text, &mut spans, &stack);
+    spans
+}
+
+fn unescape(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        s.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let c = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => name
+                    .strip_prefix('#')
+                    .and_then(|n| n.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        });
+
+        match (c, entity) {
+            (Some(c), Some((_, end))) => {
+                s.push(c);
+                rest = &rest[end + 1..];
+            }
+            // not an entity, so leave it as it is
+            _ => {
+                s.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    s.push_str(rest);
+    s
+}
+
+/// Parse the attributes of a tag, e.g.: `span foreground="#fff" size='small'`.
+fn attributes(tag: &str) -> Vec<(&str, &str)> {
+    let mut attrs = vec![];
+    // skip the tag's name
+    let mut rest = tag.trim_start_matches(|c: char| !c.is_whitespace());
+    while let Some((name, value)) = rest.split_once('=') {
+        let value = value.trim_start();
+        let quote = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => q,
+            _ => break,
+        };
+
+        let value = &value[1..];
+        let end = match value.find(quote) {
+            Some(end) => end,
+            None => break,
+        };
+
+        attrs.push((name.trim(), &value[..end]));
+        rest = &value[end + 1..];
+    }
+
+    attrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, fg: Option<&str>, bg: Option<&str>) -> Span {
+        Span {
+            text: text.into(),
+            fg: fg.map(|c| HexColor::parse(c).unwrap()),
+            bg: bg.map(|c| HexColor::parse(c).unwrap()),
+        }
+    }
+
+    #[test]
+    fn parse_markup() {
+        assert_eq!(parse("plain"), [span("plain", None, None)]);
+        assert_eq!(
+            parse(
+                r##"a <span foreground="#ff0000">b <b>c</b></span> <span bgcolor='#00ff00' size="small">d</span>"##
+            ),
+            [
+                span("a ", None, None),
+                span("b c", Some("#ff0000"), None),
+                span(" ", None, None),
+                span("d", None, Some("#00ff00")),
+            ]
+        );
+        assert_eq!(
+            parse(r#"<span foreground="red">named colours are ignored</span>"#),
+            [span("named colours are ignored", None, None)]
+        );
+    }
+
+    #[test]
+    fn entities() {
+        let strip = |markup| {
+            parse(markup)
+                .into_iter()
+                .map(|span| span.text)
+                .collect::<String>()
+        };
+        assert_eq!(
+            strip("&lt;b&gt; &amp; &#65;&#x42; &unknown; & x"),
+            "<b> & AB &unknown; & x"
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use super::{separator, spans, visible, Output};
+use crate::i3::I3Item;
+
+/// Just the text of each item, without any formatting.
+#[derive(Debug)]
+pub struct Plain;
+
+impl Output for Plain {
+    fn format(&self, bar: &[I3Item]) -> String {
+        let mut line = String::new();
+        let mut items = visible(bar).peekable();
+        while let Some(item) = items.next() {
+            line.extend(spans(item).into_iter().map(|span| span.text));
+            if items.peek().is_some() {
+                line.push_str(separator(item));
+            }
+        }
+
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i3::I3Markup;
+
+    #[test]
+    fn format() {
+        let bar = [
+            I3Item::new("<b>a</b> &amp; b").markup(I3Markup::Pango),
+            I3Item::empty(),
+            I3Item::new("<c>").separator(false),
+            I3Item::new("d"),
+        ];
+
+        assert_eq!(Plain.format(&bar), "a & b | <c> d");
+    }
+}
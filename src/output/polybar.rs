@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use hex_color::HexColor;
+
+use super::{argb, separator, spans, visible, Output};
+use crate::i3::I3Item;
+
+/// Text with formatting tags, which are the same for polybar and lemonbar.
+///
+/// Clicking an item runs `istat-ipc click`, since these bars don't send click events. Polybar runs the
+/// commands itself, but lemonbar prints them so its output should be piped to `sh`.
+#[derive(Debug)]
+pub struct Polybar {
+    socket: PathBuf,
+}
+
+impl Polybar {
+    const BUTTONS: &[(u8, &str)] = &[
+        (1, "left"),
+        (2, "middle"),
+        (3, "right"),
+        (4, "scroll_up"),
+        (5, "scroll_down"),
+    ];
+
+    pub fn new(socket: PathBuf) -> Polybar {
+        Polybar { socket }
+    }
+
+    /// Open the click actions for an item, the `:` in commands must be escaped since it ends the tag.
+    fn actions(&self, instance: &str) -> String {
+        let socket = self.socket.display().to_string().replace('\'', r"'\''");
+        Self::BUTTONS
+            .iter()
+            .map(|(n, button)| {
+                let cmd = format!(
+                    "istat-ipc --socket '{}' click {} {}",
+                    socket, instance, button
+                );
+                format!("%{{A{}:{}:}}", n, cmd.replace(':', r"\:"))
+            })
+            .collect()
+    }
+
+    fn color_tag(tag: char, color: Option<HexColor>) -> String {
+        match color {
+            Some(color) => format!("%{{{}{}}}", tag, argb(&color)),
+            None => format!("%{{{}-}}", tag),
+        }
+    }
+}
+
+impl Output for Polybar {
+    fn format(&self, bar: &[I3Item]) -> String {
+        let mut line = String::new();
+        let mut items = visible(bar).peekable();
+        while let Some(item) = items.next() {
+            let instance = item.get_instance();
+            if let Some(instance) = instance {
+                line.push_str(&self.actions(instance));
+            }
+
+            let (mut fg, mut bg) = (None, None);
+            for span in spans(item) {
+                if span.fg != fg {
+                    fg = span.fg;
+                    line.push_str(&Self::color_tag('F', fg));
+                }
+                if span.bg != bg {
+                    bg = span.bg;
+                    line.push_str(&Self::color_tag('B', bg));
+                }
+
+                // a literal `%{` would otherwise start a tag
+                line.push_str(&span.text.replace("%{", "%%{"));
+            }
+
+            if fg.is_some() {
+                line.push_str(&Self::color_tag('F', None));
+            }
+            if bg.is_some() {
+                line.push_str(&Self::color_tag('B', None));
+            }
+
+            if instance.is_some() {
+                line.push_str(&"%{A}".repeat(Self::BUTTONS.len()));
+            }
+
+            if items.peek().is_some() {
+                line.push_str(separator(item));
+            }
+        }
+
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i3::I3Markup;
+
+    #[test]
+    fn format() {
+        let red = HexColor::parse("#ff0000").unwrap();
+        let bar = [
+            I3Item::new(r##"a <span foreground="#00ff00">b</span>"##)
+                .markup(I3Markup::Pango)
+                .color(red),
+            I3Item::new("100%{").background_color(HexColor::parse("#0000ff80").unwrap()),
+        ];
+
+        assert_eq!(
+            Polybar::new("/tmp/socket".into()).format(&bar),
+            "%{F#ff0000}a %{F#00ff00}b%{F-} | %{B#800000ff}100%%{%{B-}"
+        );
+    }
+
+    #[test]
+    fn actions() {
+        let bar = [I3Item::new("a").instance("0")];
+        let line = Polybar::new("/tmp/istat:socket".into()).format(&bar);
+        assert!(line.starts_with(r"%{A1:istat-ipc --socket '/tmp/istat\:socket' click 0 left:}"));
+        assert!(line.ends_with("a%{A}%{A}%{A}%{A}%{A}"));
+    }
+}
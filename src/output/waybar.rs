@@ -0,0 +1,112 @@
+use hex_color::HexColor;
+use serde_json::json;
+
+use super::{separator, visible, Output};
+use crate::i3::{I3Item, I3Markup};
+use crate::util::escape;
+
+/// JSON for a waybar custom module, see: https://github.com/Alexays/Waybar/wiki/Module:-Custom
+///
+/// Each module shows a single line of text, so to show items as separate modules run `istat` once for
+/// each of them with `--item`. The item names are used as the module's classes, along with `urgent`.
+#[derive(Debug)]
+pub struct Waybar;
+
+impl Output for Waybar {
+    fn format(&self, bar: &[I3Item]) -> String {
+        let mut text = String::new();
+        let mut class = vec![];
+        let mut items = visible(bar).peekable();
+        while let Some(item) = items.next() {
+            // waybar renders pango markup itself, so the item's own markup is kept as it is
+            let markup = match item.get_markup() {
+                Some(I3Markup::Pango) => item.full_text.clone(),
+                _ => escape(&item.full_text),
+            };
+
+            let mut attrs = String::new();
+            if let Some(fg) = item.get_color() {
+                attrs.push_str(&format!(r#" foreground="{}""#, rgba(fg)));
+            }
+            if let Some(bg) = item.get_background_color() {
+                attrs.push_str(&format!(r#" background="{}""#, rgba(bg)));
+            }
+
+            match attrs.is_empty() {
+                true => text.push_str(&markup),
+                false => text.push_str(&format!("<span{}>{}</span>", attrs, markup)),
+            }
+
+            if items.peek().is_some() {
+                text.push_str(separator(item));
+            }
+
+            let name = item.get_name().map(|name| name.as_str());
+            let urgent = (item.get_urgent() == Some(&true)).then_some("urgent");
+            for name in [name, urgent].into_iter().flatten() {
+                if !class.contains(&name) {
+                    class.push(name);
+                }
+            }
+        }
+
+        json!({ "text": text, "class": class }).to_string()
+    }
+}
+
+/// Format a colour as `#RRGGBB`, or `#RRGGBBAA` if it's transparent (the format used by pango).
+fn rgba(color: &HexColor) -> String {
+    match color.a {
+        u8::MAX => format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b),
+        a => format!("#{:02x}{:02x}{:02x}{:02x}", color.r, color.g, color.b, a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let bar = [
+            I3Item::new(r##"<b>cpu</b> <span color="#ff0000">50%</span> &amp;"##)
+                .markup(I3Markup::Pango)
+                .name("cpu")
+                .urgent(true)
+                .background_color(HexColor::parse("#000000").unwrap()),
+        ];
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&Waybar.format(&bar)).unwrap(),
+            json!({
+                "text": r##"<span background="#000000"><b>cpu</b> <span color="#ff0000">50%</span> &amp;</span>"##,
+                "class": ["cpu", "urgent"],
+            })
+        );
+
+        let bar = [
+            I3Item::new("a & b")
+                .name("plain")
+                .color(HexColor::rgba(0x11, 0x22, 0x33, 0x44)),
+            I3Item::new("c").name("plain"),
+        ];
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&Waybar.format(&bar)).unwrap(),
+            json!({
+                "text": r##"<span foreground="#11223344">a &amp; b</span> | c"##,
+                "class": ["plain"],
+            })
+        );
+
+        // classes are only added once, even if they're not next to each other
+        let bar = [
+            I3Item::new("a").name("a").urgent(true),
+            I3Item::new("b").name("b").urgent(true),
+            I3Item::new("c").name("a"),
+        ];
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&Waybar.format(&bar)).unwrap()["class"],
+            json!(["a", "urgent", "b"])
+        );
+    }
+}
@@ -1,9 +1,9 @@
-use std::collections::HashMap;
 use std::error::Error;
 
-use libc::{SIGRTMAX, SIGRTMIN, SIGTERM};
+use libc::{SIGCONT, SIGHUP, SIGRTMAX, SIGRTMIN, SIGTERM, SIGTSTP};
 use signal_hook_tokio::{Handle, Signals};
 
+use crate::bar::Bar;
 use crate::config::AppConfig;
 use crate::context::BarEvent;
 use crate::dispatcher::Dispatcher;
@@ -16,40 +16,12 @@ use crate::util::RcCell;
 pub fn handle_signals(
     config: RcCell<AppConfig>,
     dispatcher: RcCell<Dispatcher>,
+    mut bar: RcCell<Bar>,
 ) -> Result<Handle, Box<dyn Error>> {
     let min = SIGRTMIN();
     let max = SIGRTMAX();
-    let realtime_signals = min..=max;
 
-    let mut sig_to_indices: HashMap<i32, Vec<usize>> = HashMap::new();
-    for (idx, item) in config.items.iter().enumerate() {
-        if let Some(sig) = item.common.signal {
-            // signals are passed in from 0..(SIGRTMAX - SIGRTMIN)
-            let translated_sig = min + sig as i32;
-            // make sure all signals are valid
-            if !realtime_signals.contains(&translated_sig) {
-                bail!(
-                    "Invalid signal: {}. Valid signals range from 0 up to {} inclusive",
-                    sig,
-                    max - min
-                );
-            }
-
-            log::debug!(
-                "mapping signal {} ({}) to item: {} ({})",
-                sig,
-                translated_sig,
-                idx,
-                item.name()
-            );
-            sig_to_indices
-                .entry(translated_sig)
-                .and_modify(|v| v.push(idx))
-                .or_insert_with(|| vec![idx]);
-        }
-    }
-
-    let mut signals = Signals::new(realtime_signals.chain([SIGTERM]))?;
+    let mut signals = Signals::new((min..=max).chain([SIGTERM, SIGHUP, SIGTSTP, SIGCONT]))?;
     let handle = signals.handle();
     let socket_path = config.socket();
     tokio::task::spawn_local(async move {
@@ -63,26 +35,50 @@ pub fn handle_signals(
                     let _ = std::fs::remove_file(&socket_path);
                     std::process::exit(0);
                 }
+                // i3 sends these when the bar is hidden or shown again (see `I3BarHeader`)
+                Some(SIGTSTP) => bar.stop(),
+                Some(SIGCONT) => {
+                    if let Err(e) = bar.cont().await {
+                        log::warn!("failed to refresh items: {}", e);
+                    }
+                }
+                // reload the configuration
+                Some(SIGHUP) => {
+                    log::info!("received SIGHUP, reloading config");
+                    if let Err(e) = bar.reload().await {
+                        log::error!("failed to reload config: {}", e);
+                    }
+                }
                 // any other signal will be a realtime signal
                 Some(signal) => {
+                    // signals are passed in from 0..(SIGRTMAX - SIGRTMIN)
+                    let sig = (signal - min) as u32;
+
                     // find all items which are listening for this signal
-                    match sig_to_indices.get(&signal) {
-                        // send signal event to all items
-                        Some(indices) => {
-                            for idx in indices {
-                                if let Err(e) =
-                                    dispatcher.send_bar_event(*idx, BarEvent::Signal).await
-                                {
-                                    log::warn!("failed to send signal: {}", e);
-                                    continue;
-                                }
-                            }
-                        }
-                        None => {
-                            log::warn!(
-                                "received signal: SIGRTMIN+{} but no item is expecting it",
-                                signal - min
-                            );
+                    // NOTE: this is done each time, since the items may have changed if the config was reloaded
+                    let indices = config
+                        .items
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(idx, item)| match item.common.signal {
+                            Some(s) if s == sig => Some(idx),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+
+                    if indices.is_empty() {
+                        log::warn!(
+                            "received signal: SIGRTMIN+{} but no item is expecting it",
+                            sig
+                        );
+                        continue;
+                    }
+
+                    // send signal event to all items
+                    for idx in indices {
+                        log::debug!("sending signal {} ({}) to item: {}", sig, signal, idx);
+                        if let Err(e) = dispatcher.send_bar_event(idx, BarEvent::Signal).await {
+                            log::warn!("failed to send signal: {}", e);
                             continue;
                         }
                     }
@@ -1,11 +1,18 @@
 use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
 
 use hex_color::HexColor;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::config::schema::HexColorSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ColorPair {
+    #[schemars(with = "HexColorSchema")]
     pub fg: HexColor,
+    #[schemars(with = "HexColorSchema")]
     pub bg: HexColor,
 }
 
@@ -15,7 +22,7 @@ impl ColorPair {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PowerlineSeparator {
     value: String,
     #[serde(default)]
@@ -31,25 +38,34 @@ impl PowerlineSeparator {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Theme {
     #[serde(default = "Theme::default_bg")]
+    #[schemars(with = "HexColorSchema")]
     pub bg: HexColor,
     #[serde(default = "Theme::default_fg")]
+    #[schemars(with = "HexColorSchema")]
     pub fg: HexColor,
     #[serde(default = "Theme::default_dim")]
+    #[schemars(with = "HexColorSchema")]
     pub dim: HexColor,
     #[serde(default = "Theme::default_red")]
+    #[schemars(with = "HexColorSchema")]
     pub red: HexColor,
     #[serde(default = "Theme::default_orange")]
+    #[schemars(with = "HexColorSchema")]
     pub orange: HexColor,
     #[serde(default = "Theme::default_yellow")]
+    #[schemars(with = "HexColorSchema")]
     pub yellow: HexColor,
     #[serde(default = "Theme::default_green")]
+    #[schemars(with = "HexColorSchema")]
     pub green: HexColor,
     #[serde(default = "Theme::default_purple")]
+    #[schemars(with = "HexColorSchema")]
     pub purple: HexColor,
     #[serde(default = "Theme::default_blue")]
+    #[schemars(with = "HexColorSchema")]
     pub blue: HexColor,
     #[serde(default = "Theme::default_powerline")]
     pub powerline: Vec<ColorPair>,
@@ -79,6 +95,22 @@ impl Default for Theme {
 }
 
 impl Theme {
+    /// Create a copy of this theme, with any colours set in the override replaced.
+    pub fn with_override(&self, theme: &ThemeOverride) -> Theme {
+        Theme {
+            bg: theme.bg.unwrap_or(self.bg),
+            fg: theme.fg.unwrap_or(self.fg),
+            dim: theme.dim.unwrap_or(self.dim),
+            red: theme.red.unwrap_or(self.red),
+            orange: theme.orange.unwrap_or(self.orange),
+            yellow: theme.yellow.unwrap_or(self.yellow),
+            green: theme.green.unwrap_or(self.green),
+            purple: theme.purple.unwrap_or(self.purple),
+            blue: theme.blue.unwrap_or(self.blue),
+            ..self.clone()
+        }
+    }
+
     pub fn validate(&self) -> Result<(), Box<dyn Error>> {
         if self.powerline.len() <= 1 {
             bail!("theme.powerline must contain at least two values");
@@ -141,3 +173,159 @@ impl Theme {
         }
     }
 }
+
+/// A partial theme for a single item, any colours which aren't set are taken from the global theme.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ThemeOverride {
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub bg: Option<HexColor>,
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub fg: Option<HexColor>,
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub dim: Option<HexColor>,
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub red: Option<HexColor>,
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub orange: Option<HexColor>,
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub yellow: Option<HexColor>,
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub green: Option<HexColor>,
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub purple: Option<HexColor>,
+    #[schemars(with = "Option<HexColorSchema>")]
+    pub blue: Option<HexColor>,
+    /// The colours used for this item when `powerline_enable` is set, rather than the next pair in
+    /// the global theme's `powerline` list
+    pub powerline: Option<ColorPair>,
+}
+
+/// A colour which is either one of the theme's named colours (e.g., `red`), or a hex colour (e.g., `#ff0000`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ThemeColor {
+    Bg,
+    Fg,
+    Dim,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Purple,
+    Blue,
+    Hex(HexColor),
+}
+
+impl ThemeColor {
+    pub fn resolve(&self, theme: &Theme) -> HexColor {
+        match self {
+            ThemeColor::Bg => theme.bg,
+            ThemeColor::Fg => theme.fg,
+            ThemeColor::Dim => theme.dim,
+            ThemeColor::Red => theme.red,
+            ThemeColor::Orange => theme.orange,
+            ThemeColor::Yellow => theme.yellow,
+            ThemeColor::Green => theme.green,
+            ThemeColor::Purple => theme.purple,
+            ThemeColor::Blue => theme.blue,
+            ThemeColor::Hex(color) => *color,
+        }
+    }
+}
+
+impl FromStr for ThemeColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bg" => Ok(ThemeColor::Bg),
+            "fg" => Ok(ThemeColor::Fg),
+            "dim" => Ok(ThemeColor::Dim),
+            "red" => Ok(ThemeColor::Red),
+            "orange" => Ok(ThemeColor::Orange),
+            "yellow" => Ok(ThemeColor::Yellow),
+            "green" => Ok(ThemeColor::Green),
+            "purple" => Ok(ThemeColor::Purple),
+            "blue" => Ok(ThemeColor::Blue),
+            s if s.starts_with('#') => HexColor::parse(s)
+                .map(ThemeColor::Hex)
+                .map_err(|e| format!("invalid hex colour: {}: {}", s, e)),
+            s => Err(format!(
+                "invalid colour: {}, expected a hex colour or one of: bg, fg, dim, red, orange, yellow, green, purple, blue",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for ThemeColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeColor::Bg => write!(f, "bg"),
+            ThemeColor::Fg => write!(f, "fg"),
+            ThemeColor::Dim => write!(f, "dim"),
+            ThemeColor::Red => write!(f, "red"),
+            ThemeColor::Orange => write!(f, "orange"),
+            ThemeColor::Yellow => write!(f, "yellow"),
+            ThemeColor::Green => write!(f, "green"),
+            ThemeColor::Purple => write!(f, "purple"),
+            ThemeColor::Blue => write!(f, "blue"),
+            ThemeColor::Hex(color) => write!(f, "{}", color),
+        }
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(value: ThemeColor) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_theme_color() {
+        let theme = Theme::default();
+        assert_eq!(
+            "red".parse::<ThemeColor>().unwrap().resolve(&theme),
+            theme.red
+        );
+        assert_eq!(
+            "#ff0000".parse::<ThemeColor>().unwrap().resolve(&theme),
+            HexColor::rgb(255, 0, 0)
+        );
+        assert!("crimson".parse::<ThemeColor>().is_err());
+        assert!("#nope".parse::<ThemeColor>().is_err());
+    }
+
+    #[test]
+    fn theme_override() {
+        let theme = Theme::default();
+        let red = HexColor::rgb(255, 0, 0);
+        let merged = theme.with_override(&ThemeOverride {
+            red: Some(red),
+            ..Default::default()
+        });
+
+        assert_eq!(merged.red, red);
+        assert_eq!(merged.fg, theme.fg);
+        assert_eq!(merged.powerline.len(), theme.powerline.len());
+    }
+
+    #[test]
+    fn theme_color_roundtrip() {
+        for color in [ThemeColor::Dim, ThemeColor::Hex(HexColor::rgb(1, 2, 3))] {
+            assert_eq!(color.to_string().parse::<ThemeColor>().unwrap(), color);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+
+/// See: https://www.kernel.org/doc/html/latest/input/event-codes.html#ev-led
+const EV_LED: u16 = 0x11;
+const INPUT_EVENT_SIZE: usize = mem::size_of::<libc::input_event>();
+
+/// Find the input devices which have LEDs (i.e., keyboards), by checking their capabilities.
+async fn find_led_devices() -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut devices = vec![];
+    let mut entries = fs::read_dir("/sys/class/input/").await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with("event") {
+            continue;
+        }
+
+        // a bitmask of the LEDs the device has, as hex
+        let leds = match fs::read_to_string(entry.path().join("device/capabilities/led")).await {
+            Ok(leds) => leds,
+            Err(_) => continue,
+        };
+        if leds.trim().trim_start_matches('0').is_empty() {
+            continue;
+        }
+
+        devices.push(PathBuf::from("/dev/input").join(name));
+    }
+
+    Ok(devices)
+}
+
+/// Watch the LEDs of input devices (e.g., caps lock), a message is sent each time one changes.
+/// Reading input devices usually requires the user to be in the `input` group.
+pub async fn evdev_led_watch() -> Result<Receiver<()>, Box<dyn Error>> {
+    let devices = find_led_devices().await?;
+    if devices.is_empty() {
+        bail!("no input devices with LEDs were found");
+    }
+
+    // open all the devices before watching any of them, so nothing is left running on failure
+    let mut opened = vec![];
+    let mut last_err = None;
+    for path in devices {
+        match open_device(&path) {
+            Ok(fd) => opened.push((path, fd)),
+            // some devices may not be readable, e.g.: if they're only readable by root, so skip those
+            Err(e) => {
+                log::debug!("skipping {}: {}", path.display(), e);
+                last_err = Some(format!("last error: {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    if opened.is_empty() {
+        let err = last_err.unwrap_or_default();
+        bail!("no input devices with LEDs could be opened, {}", err);
+    }
+
+    let (tx, rx) = mpsc::channel(1);
+    for (path, fd) in opened {
+        tokio::task::spawn_local(watch_device(path, fd, tx.clone()));
+    }
+
+    Ok(rx)
+}
+
+fn open_device(path: &Path) -> io::Result<AsyncFd<std::fs::File>> {
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+        .open(path)?;
+
+    AsyncFd::new(file)
+}
+
+async fn watch_device(path: PathBuf, fd: AsyncFd<std::fs::File>, tx: Sender<()>) {
+    let mut buf = [0; INPUT_EVENT_SIZE * 16];
+    let err = loop {
+        let mut guard = tokio::select! {
+            // the item has stopped, so stop reading the device
+            () = tx.closed() => return,
+            result = fd.readable() => match result {
+                Ok(guard) => guard,
+                Err(e) => break e,
+            },
+        };
+
+        let len = match guard.try_io(|fd| fd.get_ref().read(&mut buf)) {
+            Ok(Ok(len)) => len,
+            Ok(Err(e)) => break e,
+            // there may be false positives readiness events
+            Err(_would_block) => continue,
+        };
+
+        // the device was removed
+        if len == 0 {
+            break io::ErrorKind::UnexpectedEof.into();
+        }
+
+        // the kernel only ever returns whole events
+        let has_led_event = buf[..len].chunks_exact(INPUT_EVENT_SIZE).any(|event| {
+            // SAFETY: the chunk is the size of an event, and any bytes are a valid event
+            let event = unsafe { (event.as_ptr() as *const libc::input_event).read_unaligned() };
+            event.type_ == EV_LED
+        });
+
+        if has_led_event {
+            match tx.try_send(()) {
+                // the receiver hasn't handled the last message yet, so this one isn't needed
+                Ok(()) | Err(mpsc::error::TrySendError::Full(())) => {}
+                Err(mpsc::error::TrySendError::Closed(())) => return,
+            }
+        }
+    };
+
+    log::warn!(
+        "stopped watching {} for LED events: {}",
+        path.display(),
+        err
+    );
+}
@@ -1,13 +1,22 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
 use tokio::process::Command;
 
 /// Used when bar items need to run an external command. It won't block, and also
 /// won't return any error: it shouldn't crash the app if the child process fails
 /// in any way (just like i3 handles commands).
 pub async fn exec(cmd: impl AsRef<str>) {
+    exec_with_env(cmd, HashMap::new()).await
+}
+
+/// The same as `exec`, but with extra environment variables set for the command.
+pub async fn exec_with_env(cmd: impl AsRef<str>, env: HashMap<&str, String>) {
     let cmd = cmd.as_ref();
     log::debug!("exec: command --> {} <--", cmd);
 
-    let child = Command::new("sh").arg("-c").arg(cmd).output();
+    let child = Command::new("sh").arg("-c").arg(cmd).envs(env).output();
     match child.await {
         Ok(output) => {
             if !output.status.success() {
@@ -17,3 +26,30 @@ pub async fn exec(cmd: impl AsRef<str>) {
         Err(e) => log::error!("fail: command --> {} <-- {}", cmd, e),
     }
 }
+
+/// Find a program like a shell would: paths (anything containing a `/`) are used as they are,
+/// otherwise the program is searched for in `$PATH`.
+pub fn which(program: impl AsRef<Path>) -> Option<PathBuf> {
+    let program = program.as_ref();
+    if program.components().count() > 1 {
+        return program.is_file().then(|| program.to_path_buf());
+    }
+
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn which_program() {
+        assert!(which("sh").is_some());
+        assert!(which("/bin/sh").is_some());
+        assert!(which("this-program-does-not-exist").is_none());
+        assert!(which("./this-program-does-not-exist").is_none());
+    }
+}
@@ -1,4 +1,7 @@
+use std::str::FromStr;
+
 use num_traits::Float;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::theme::Theme;
@@ -18,7 +21,7 @@ pub fn fraction(theme: &Theme, num: usize, den: usize) -> String {
 }
 
 /// Common, re-usable options for formatting floats.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct FloatFormat {
     /// The character to use for padding.
     pad: Option<char>,
@@ -29,6 +32,48 @@ pub struct FloatFormat {
     precision: Option<usize>,
 }
 
+impl FromStr for FloatFormat {
+    type Err = String;
+
+    /// Parse a float format from a spec like `[pad][pad_count][.precision]`, e.g.: `.1`, `04` or `_3.2`.
+    /// Unlike the configuration options, no padding is done unless `pad_count` is given.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || {
+            format!(
+                "invalid float format: {}, expected it to look like: [pad][pad_count][.precision]",
+                s
+            )
+        };
+
+        let (width, precision) = match s.split_once('.') {
+            Some((width, precision)) => (width, Some(precision.parse().map_err(|_| err())?)),
+            None => (s, None),
+        };
+
+        let mut chars = width.chars();
+        let (pad, pad_count) = match chars.next() {
+            None => (None, ""),
+            // a leading zero means pad with zeros, like printf
+            Some('0') if width.len() > 1 => (Some('0'), chars.as_str()),
+            Some(c) if c.is_ascii_digit() => (None, width),
+            Some(c) => (Some(c), chars.as_str()),
+        };
+
+        let pad_count = match (pad, pad_count) {
+            (None, "") => 0,
+            // a padding character is pointless without a count
+            (Some(_), "") => return Err(err()),
+            (_, count) => count.parse().map_err(|_| err())?,
+        };
+
+        Ok(FloatFormat {
+            pad,
+            pad_count: Some(pad_count),
+            precision,
+        })
+    }
+}
+
 /// Return the number of digits (before the decimal place) a given number has.
 fn num_digits<F: Float>(n: F) -> usize {
     // SAFETY: the input type is constrained to a float, and all f32's fit into an f64
@@ -172,4 +217,23 @@ mod tests {
         assert_eq!(float(100.4, &fmt), "--100.400");
         assert_eq!(float(99999.999, &fmt), "99999.999");
     }
+
+    #[test]
+    fn parse() {
+        let parse = |s: &str| s.parse::<FloatFormat>().unwrap();
+        assert_eq!(float(1.24, &parse("")), "1");
+        assert_eq!(float(1.24, &parse("0")), "1");
+        assert_eq!(float(1.24, &parse(".1")), "1.2");
+        assert_eq!(float(1.24, &parse("3")), "  1");
+        assert_eq!(float(1.24, &parse("03")), "001");
+        assert_eq!(float(1.24, &parse("_4.2")), "___1.24");
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!("x".parse::<FloatFormat>().is_err());
+        assert!("3.".parse::<FloatFormat>().is_err());
+        assert!("3.x".parse::<FloatFormat>().is_err());
+        assert!("a1b".parse::<FloatFormat>().is_err());
+    }
 }
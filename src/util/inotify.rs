@@ -0,0 +1,47 @@
+use std::error::Error;
+use std::io;
+use std::path::Path;
+
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc::{self, Receiver};
+
+/// Watch a file with inotify, a message is sent each time one of the given events happens to it.
+pub async fn inotify_watch(
+    path: impl AsRef<Path>,
+    flags: AddWatchFlags,
+) -> Result<Receiver<()>, Box<dyn Error>> {
+    let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+    inotify.add_watch(path.as_ref(), flags)?;
+    let fd = AsyncFd::new(inotify)?;
+
+    let (tx, rx) = mpsc::channel(1);
+    tokio::task::spawn_local(async move {
+        let err = loop {
+            let mut guard = tokio::select! {
+                // the item has stopped, so stop watching the file
+                () = tx.closed() => return,
+                result = fd.readable() => match result {
+                    Ok(guard) => guard,
+                    Err(e) => break e,
+                },
+            };
+
+            match guard.try_io(|fd| fd.get_ref().read_events().map_err(io::Error::from)) {
+                // several events may be read at once, but only one message is sent for them
+                Ok(Ok(_)) => match tx.try_send(()) {
+                    // the receiver hasn't handled the last message yet, so this one isn't needed
+                    Ok(()) | Err(mpsc::error::TrySendError::Full(())) => {}
+                    Err(mpsc::error::TrySendError::Closed(())) => return,
+                },
+                Ok(Err(e)) => break e,
+                // there may be false positives readiness events
+                Err(_would_block) => continue,
+            }
+        };
+
+        log::error!("unexpected failure of inotify event stream: {}", err);
+    });
+
+    Ok(rx)
+}
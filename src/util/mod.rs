@@ -1,4 +1,7 @@
-use_and_export!(cell, enum_cycle, exec, format, net, netlink, paginator, vec);
+use_and_export!(
+    cell, enum_cycle, evdev, exec, format, inotify, markup, net, netlink, paginator, template,
+    thresholds, vec
+);
 
 use std::error::Error;
 
@@ -6,6 +9,11 @@ use futures::Future;
 use tokio::runtime::{Builder, Runtime};
 use tokio::task::LocalSet;
 
+#[cfg(feature = "xkb")]
+pub mod xkb;
+#[cfg(feature = "xkb")]
+pub use xkb::*;
+
 /// Block on a given future, running it on the current thread inside a `LocalSet`.
 pub fn local_block_on<F>(f: F) -> Result<(F::Output, Runtime), Box<dyn Error>>
 where
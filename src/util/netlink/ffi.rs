@@ -45,6 +45,8 @@ impl AcpiGenericNetlinkEvent {
     pub const DEVICE_CLASS_AC: &str = "ac_adapter";
     /// https://github.com/torvalds/linux/blob/f8dba31b0a826e691949cd4fdfa5c30defaac8c5/include/acpi/processor.h#L17
     pub const DEVICE_CLASS_PROCESSOR: &str = "processor";
+    /// https://github.com/torvalds/linux/blob/f8dba31b0a826e691949cd4fdfa5c30defaac8c5/drivers/acpi/acpi_video.c
+    pub const DEVICE_CLASS_VIDEO: &str = "video";
 }
 
 /// Checks a slice of C's chars to ensure they're not signed, needed because C's `char` type could
@@ -1,4 +1,5 @@
 use super::fraction;
+use crate::config::click::ItemAction;
 use crate::context::BarEvent;
 use crate::i3::I3Button::*;
 use crate::theme::Theme;
@@ -47,6 +48,8 @@ impl Paginator {
         match event {
             BarEvent::Click(c) if matches!(c.button, Left | ScrollUp) => self.incr(),
             BarEvent::Click(c) if matches!(c.button, Right | ScrollDown) => self.decr(),
+            BarEvent::Action(ItemAction::PaginatorNext) => self.incr(),
+            BarEvent::Action(ItemAction::PaginatorPrev) => self.decr(),
             _ => {}
         }
     }
@@ -0,0 +1,400 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::format::{float, FloatFormat};
+use super::markup::escape;
+use crate::i3::{I3Item, I3Markup};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Placeholder {
+        name: String,
+        fmt: Option<FloatFormat>,
+    },
+}
+
+/// A format string which is rendered with values provided by an item, for example: `CPU {pct}%`.
+///
+/// Placeholders are written as `{name}`, and numeric placeholders can override the item's float
+/// format with `{name:<pad><pad_count>.<precision>}`, e.g.: `{pct:.1}`, `{pct:04}` or `{pct:_3.2}`.
+/// Use `{{` and `}}` to write literal braces. Pango markup is passed through untouched, and when the
+/// item uses pango markup any text from placeholders is escaped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Render the template, any placeholders which the item doesn't provide are left as they are.
+    /// If `pango` is set, then the output is pango markup, so text is escaped as needed.
+    pub fn render(&self, placeholders: &Placeholders, pango: bool) -> String {
+        let escape_text = |text: &str| match pango {
+            true => escape(text),
+            false => text.to_string(),
+        };
+
+        let mut s = String::new();
+        for part in &self.parts {
+            match part {
+                // the template's own text is only escaped if it isn't markup itself
+                Part::Text(text) if self.has_markup() => s.push_str(text),
+                Part::Text(text) => s.push_str(&escape_text(text)),
+                Part::Placeholder { name, fmt } => match placeholders.get(name) {
+                    Some(Value::Text(text)) => s.push_str(&escape_text(text)),
+                    Some(Value::Markup(markup)) => s.push_str(markup),
+                    Some(Value::Float(n, default_fmt)) => {
+                        s.push_str(&float(*n, fmt.as_ref().unwrap_or(default_fmt)))
+                    }
+                    None => {
+                        s.push('{');
+                        s.push_str(name);
+                        s.push('}');
+                    }
+                },
+            }
+        }
+
+        s
+    }
+
+    /// Whether the template uses any of the item's values.
+    pub fn has_placeholders(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|p| matches!(p, Part::Placeholder { .. }))
+    }
+
+    /// Whether the template contains pango markup, i.e.: any tags such as `<b>` or `</span>`. A `<`
+    /// on its own (e.g., `a < b`) isn't markup.
+    fn has_markup(&self) -> bool {
+        self.parts.iter().any(|p| match p {
+            Part::Text(text) => text.match_indices('<').any(|(idx, _)| {
+                let tag = text[idx + 1..].trim_start_matches('/');
+                tag.starts_with(|c: char| c.is_ascii_alphabetic()) && tag.contains('>')
+            }),
+            Part::Placeholder { .. } => false,
+        })
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => return Err(format!("unclosed placeholder in format: {}", s)),
+                        }
+                    }
+
+                    let (name, fmt) = match inner.split_once(':') {
+                        Some((name, fmt)) => (name, Some(fmt.parse::<FloatFormat>()?)),
+                        None => (inner.as_str(), None),
+                    };
+
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(format!("empty placeholder in format: {}", s));
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Placeholder {
+                        name: name.into(),
+                        fmt,
+                    });
+                }
+                '}' => {
+                    return Err(format!(
+                        "unmatched `}}` in format: {}, use `}}}}` instead",
+                        s
+                    ))
+                }
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Template {
+            source: s.into(),
+            parts,
+        })
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl From<Template> for String {
+    fn from(value: Template) -> Self {
+        value.source
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    /// Text which is already pango markup, so it's never escaped.
+    Markup(String),
+    Float(f64, FloatFormat),
+}
+
+/// The values an item provides for use in its `format` and `format_short` options.
+#[derive(Debug, Default, Clone)]
+pub struct Placeholders {
+    values: Vec<(&'static str, Value)>,
+}
+
+impl Placeholders {
+    pub fn new() -> Placeholders {
+        Placeholders::default()
+    }
+
+    pub fn text(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.values.push((name, Value::Text(value.to_string())));
+        self
+    }
+
+    /// A value which is already pango markup (e.g., the page from a `Paginator`), so it's not escaped.
+    pub fn markup(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.values.push((name, Value::Markup(value.to_string())));
+        self
+    }
+
+    /// Numeric placeholders are formatted with the given float format, unless it's overridden in the template.
+    pub fn float(mut self, name: &'static str, value: impl Into<f64>, fmt: &FloatFormat) -> Self {
+        self.values
+            .push((name, Value::Float(value.into(), fmt.clone())));
+        self
+    }
+
+    /// Add the item's own text as `{full_text}` and `{short_text}`, these are available for all items.
+    pub fn item_text(mut self, item: &I3Item) -> Self {
+        let short_text = item.get_short_text().unwrap_or(&item.full_text).clone();
+        let value = match item.get_markup() {
+            Some(I3Markup::Pango) => Value::Markup,
+            _ => Value::Text,
+        };
+        self.values
+            .push(("full_text", value(item.full_text.clone())));
+        self.values.push(("short_text", value(short_text)));
+        self
+    }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.values.iter().find(|(n, _)| *n == name).map(|(_, v)| v)
+    }
+
+    /// Get a value as a number, text values are parsed if possible.
+    pub fn number(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            Value::Float(n, _) => Some(*n),
+            Value::Text(text) | Value::Markup(text) => text.trim().parse().ok(),
+        }
+    }
+
+    /// Get a value as text, numbers are formatted with their float format.
+    pub fn text_value(&self, name: &str) -> Option<String> {
+        match self.get(name)? {
+            Value::Float(n, fmt) => Some(float(*n, fmt)),
+            Value::Text(text) | Value::Markup(text) => Some(text.clone()),
+        }
+    }
+}
+
+/// The user's `format` and `format_short` options for an item.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ItemFormat {
+    /// Replaces the item's `full_text`.
+    pub format: Option<Template>,
+    /// Replaces the item's `short_text`.
+    pub format_short: Option<Template>,
+}
+
+impl ItemFormat {
+    /// Render the user's templates into the item.
+    pub fn apply(&self, mut item: I3Item, placeholders: &Placeholders) -> I3Item {
+        let pango = item.get_markup() == Some(&I3Markup::Pango)
+            || [&self.format, &self.format_short]
+                .into_iter()
+                .flatten()
+                .any(Template::has_markup);
+
+        if let Some(template) = &self.format {
+            item = item.full_text(template.render(placeholders, pango));
+        }
+        if let Some(template) = &self.format_short {
+            item = item.short_text(template.render(placeholders, pango));
+        }
+
+        if pango {
+            item = item.markup(I3Markup::Pango);
+        }
+
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, placeholders: &Placeholders) -> String {
+        template
+            .parse::<Template>()
+            .unwrap()
+            .render(placeholders, false)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "a {b} c".parse::<Template>().unwrap().parts,
+            [
+                Part::Text("a ".into()),
+                Part::Placeholder {
+                    name: "b".into(),
+                    fmt: None
+                },
+                Part::Text(" c".into()),
+            ]
+        );
+        assert_eq!(
+            "{{{b:.1}}}".parse::<Template>().unwrap().parts,
+            [
+                Part::Text("{".into()),
+                Part::Placeholder {
+                    name: "b".into(),
+                    fmt: Some(".1".parse().unwrap())
+                },
+                Part::Text("}".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!("{".parse::<Template>().is_err());
+        assert!("}".parse::<Template>().is_err());
+        assert!("{}".parse::<Template>().is_err());
+        assert!("{a:x}".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn render_text() {
+        let p = Placeholders::new().text("mount", "/home");
+        assert_eq!(render("<b>{mount}</b>", &p), "<b>/home</b>");
+        assert_eq!(render("{mount} {unknown}", &p), "/home {unknown}");
+        assert_eq!(render("{{mount}}", &p), "{mount}");
+    }
+
+    #[test]
+    fn render_float() {
+        let fmt = FloatFormat::default();
+        let p = Placeholders::new().float("pct", 42.123, &fmt);
+        assert_eq!(render("{pct}%", &p), " 42%");
+        assert_eq!(render("{pct:.1}%", &p), "42.1%");
+        assert_eq!(render("{pct:05.2}%", &p), "00042.12%");
+    }
+
+    #[test]
+    fn apply() {
+        let format = ItemFormat {
+            format: Some("[{full_text}] {pct:.0}%".parse().unwrap()),
+            format_short: Some("<i>{short_text}</i>".parse().unwrap()),
+        };
+        let item = I3Item::new("full").short_text("short");
+        let placeholders = Placeholders::new()
+            .float("pct", 10.0, &FloatFormat::default())
+            .item_text(&item);
+        let item = format.apply(item, &placeholders);
+        assert_eq!(item.full_text, "[full] 10%");
+        assert_eq!(item.get_short_text().unwrap(), "<i>short</i>");
+        assert_eq!(item.get_markup(), Some(&I3Markup::Pango));
+
+        let item = ItemFormat::default().apply(I3Item::new("full"), &Placeholders::new());
+        assert_eq!(item, I3Item::new("full"));
+    }
+
+    #[test]
+    fn apply_escapes_pango() {
+        let format = ItemFormat {
+            format: Some("<b>{title}</b> {full_text}".parse().unwrap()),
+            format_short: Some("{title} & 1 < 2".parse().unwrap()),
+        };
+        let item = I3Item::new("<i>a&amp;b</i>").markup(I3Markup::Pango);
+        let placeholders = Placeholders::new()
+            .text("title", "Rock & <Roll>")
+            .item_text(&item);
+        let item = format.apply(item, &placeholders);
+        // the item's own text is already markup, so it isn't escaped again
+        assert_eq!(
+            item.full_text,
+            "<b>Rock &amp; &lt;Roll&gt;</b> <i>a&amp;b</i>"
+        );
+        assert_eq!(
+            item.get_short_text().unwrap(),
+            "Rock &amp; &lt;Roll&gt; &amp; 1 &lt; 2"
+        );
+
+        // nothing is escaped without markup, and a lone `<` isn't markup
+        let format = ItemFormat {
+            format: Some("{title} < 2".parse().unwrap()),
+            format_short: None,
+        };
+        let placeholders = Placeholders::new().text("title", "a & b");
+        let item = format.apply(I3Item::new(""), &placeholders);
+        assert_eq!(item.full_text, "a & b < 2");
+        assert_eq!(item.get_markup(), None);
+    }
+
+    #[test]
+    fn apply_markup_placeholder() {
+        let format = ItemFormat {
+            format: Some("{pct} {page}".parse().unwrap()),
+            format_short: None,
+        };
+        let page = "<span><sup>1</sup>/<sub>2</sub></span>";
+        let placeholders = Placeholders::new().text("pct", "50%").markup("page", page);
+        let item = format.apply(I3Item::new("").markup(I3Markup::Pango), &placeholders);
+        // markup values are passed through, even though the item's text is escaped
+        assert_eq!(item.full_text, format!("50% {}", page));
+    }
+}
@@ -0,0 +1,175 @@
+use std::error::Error;
+
+use hex_color::HexColor;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::i3::I3Item;
+use crate::theme::{Theme, ThemeColor};
+
+/// A single threshold, which applies to any value greater than or equal to `value`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Threshold<T> {
+    pub value: T,
+    /// The colour to use, if not set then the item's default colour is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<ThemeColor>,
+    /// Mark the item as urgent.
+    #[serde(default)]
+    pub urgent: bool,
+}
+
+impl<T> Threshold<T> {
+    pub fn new(value: T, color: Option<ThemeColor>) -> Threshold<T> {
+        Threshold {
+            value,
+            color,
+            urgent: false,
+        }
+    }
+
+    pub fn urgent(mut self, urgent: bool) -> Self {
+        self.urgent = urgent;
+        self
+    }
+
+    pub fn get_color(&self, theme: &Theme) -> Option<HexColor> {
+        self.color.map(|c| c.resolve(theme))
+    }
+}
+
+/// An ordered list of thresholds used to colour items, for example:
+/// `thresholds = [{ value = 60, color = "orange" }, { value = 80, color = "#ff0000", urgent = true }]`
+///
+/// The last threshold which a value reaches is the one chosen, and values below the first threshold
+/// use the item's default colour.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(bound = "T: JsonSchema")]
+#[serde(
+    try_from = "Vec<Threshold<T>>",
+    into = "Vec<Threshold<T>>",
+    bound(
+        serialize = "T: Clone + serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de> + PartialOrd"
+    )
+)]
+pub struct Thresholds<T = f64>(Vec<Threshold<T>>);
+
+impl<T> Default for Thresholds<T> {
+    fn default() -> Self {
+        Thresholds(vec![])
+    }
+}
+
+impl<T: PartialOrd> Thresholds<T> {
+    pub fn new(thresholds: Vec<Threshold<T>>) -> Result<Thresholds<T>, Box<dyn Error>> {
+        if thresholds.windows(2).any(|w| w[0].value > w[1].value) {
+            bail!("thresholds must be in ascending order of their values");
+        }
+
+        Ok(Thresholds(thresholds))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Find the threshold which applies to the given value.
+    pub fn find(&self, value: &T) -> Option<&Threshold<T>> {
+        self.0.iter().rev().find(|t| t.value <= *value)
+    }
+
+    pub fn get_color(&self, theme: &Theme, value: &T) -> Option<HexColor> {
+        self.find(value).and_then(|t| t.get_color(theme))
+    }
+
+    /// Set the item's colour and urgency from the threshold which applies to the given value.
+    pub fn apply(&self, theme: &Theme, value: &T, mut item: I3Item) -> I3Item {
+        if let Some(threshold) = self.find(value) {
+            if let Some(color) = threshold.get_color(theme) {
+                item = item.color(color);
+            }
+            if threshold.urgent {
+                item = item.urgent(true);
+            }
+        }
+
+        item
+    }
+}
+
+impl<T: PartialOrd> TryFrom<Vec<Threshold<T>>> for Thresholds<T> {
+    type Error = String;
+
+    fn try_from(value: Vec<Threshold<T>>) -> Result<Self, Self::Error> {
+        Thresholds::new(value).map_err(|e| e.to_string())
+    }
+}
+
+impl<T> From<Thresholds<T>> for Vec<Threshold<T>> {
+    fn from(value: Thresholds<T>) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn thresholds() -> Thresholds {
+        Thresholds::new(vec![
+            Threshold::new(40.0, Some(ThemeColor::Yellow)),
+            Threshold::new(60.0, None),
+            Threshold::new(80.0, Some(ThemeColor::Red)).urgent(true),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn find() {
+        let t = thresholds();
+        assert_eq!(t.find(&0.0), None);
+        assert_eq!(t.find(&40.0).unwrap().value, 40.0);
+        assert_eq!(t.find(&59.9).unwrap().value, 40.0);
+        assert_eq!(t.find(&60.0).unwrap().value, 60.0);
+        assert_eq!(t.find(&100.0).unwrap().value, 80.0);
+    }
+
+    #[test]
+    fn apply() {
+        let theme = Theme::default();
+        let t = thresholds();
+        assert_eq!(t.apply(&theme, &10.0, I3Item::new("")), I3Item::new(""));
+        assert_eq!(
+            t.apply(&theme, &50.0, I3Item::new("")),
+            I3Item::new("").color(theme.yellow)
+        );
+        assert_eq!(t.apply(&theme, &70.0, I3Item::new("")), I3Item::new(""));
+        assert_eq!(
+            t.apply(&theme, &90.0, I3Item::new("")),
+            I3Item::new("").color(theme.red).urgent(true)
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        let t = serde_json::from_value::<Thresholds>(json!([
+            { "value": 40, "color": "yellow" },
+            { "value": 60 },
+            { "value": 80, "color": "red", "urgent": true },
+        ]))
+        .unwrap();
+        assert_eq!(t, thresholds());
+    }
+
+    #[test]
+    fn deserialize_unordered() {
+        assert!(serde_json::from_value::<Thresholds>(json!([
+            { "value": 80, "color": "red" },
+            { "value": 40, "color": "yellow" },
+        ]))
+        .is_err());
+    }
+}
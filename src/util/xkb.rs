@@ -0,0 +1,145 @@
+use std::error::Error;
+
+use tokio::io::unix::AsyncFd;
+use xcb::{x, xkb, Connection, Extension, Xid};
+
+const DEVICE: xkb::DeviceSpec = xkb::Id::UseCoreKbd as xkb::DeviceSpec;
+
+/// A connection to the X server, used to read and change the keyboard's state with XKB.
+pub struct Xkb {
+    conn: AsyncFd<Connection>,
+}
+
+impl Xkb {
+    /// Connect to the X server (from `DISPLAY`), and listen for changes to the keyboard's state.
+    pub fn connect() -> Result<Xkb, Box<dyn Error>> {
+        let (conn, _) = Connection::connect_with_extensions(None, &[Extension::Xkb], &[])?;
+
+        let reply = conn.wait_for_reply(conn.send_request(&xkb::UseExtension {
+            wanted_major: 1,
+            wanted_minor: 0,
+        }))?;
+        if !reply.supported() {
+            bail!("the X server doesn't support XKB");
+        }
+
+        // only select the details which change the active group or the group names, otherwise
+        // every key press (modifiers, etc) would wake up the item
+        conn.send_and_check_request(&xkb::SelectEvents {
+            device_spec: DEVICE,
+            affect_which: xkb::EventType::STATE_NOTIFY | xkb::EventType::NAMES_NOTIFY,
+            clear: xkb::EventType::empty(),
+            select_all: xkb::EventType::empty(),
+            affect_map: xkb::MapPart::empty(),
+            map: xkb::MapPart::empty(),
+            details: &[
+                xkb::SelectEventsDetails::StateNotify {
+                    affect_state: xkb::StatePart::GROUP_STATE,
+                    state_details: xkb::StatePart::GROUP_STATE,
+                },
+                xkb::SelectEventsDetails::NamesNotify {
+                    affect_names: xkb::NameDetail::GROUP_NAMES,
+                    names_details: xkb::NameDetail::GROUP_NAMES,
+                },
+            ],
+        })?;
+
+        Ok(Xkb {
+            conn: AsyncFd::new(conn)?,
+        })
+    }
+
+    /// The index of the active group (i.e., keyboard layout).
+    pub fn group(&self) -> Result<usize, Box<dyn Error>> {
+        let conn = self.conn.get_ref();
+        let state = conn.wait_for_reply(conn.send_request(&xkb::GetState {
+            device_spec: DEVICE,
+        }))?;
+
+        Ok(match state.group() {
+            xkb::Group::N1 => 0,
+            xkb::Group::N2 => 1,
+            xkb::Group::N3 => 2,
+            xkb::Group::N4 => 3,
+        })
+    }
+
+    /// The names of each group, e.g.: `English (US)`.
+    pub fn group_names(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let conn = self.conn.get_ref();
+        let names = conn.wait_for_reply(conn.send_request(&xkb::GetNames {
+            device_spec: DEVICE,
+            which: xkb::NameDetail::GROUP_NAMES,
+        }))?;
+
+        let atoms = names
+            .value_list()
+            .into_iter()
+            .find_map(|value| match value {
+                xkb::GetNamesReplyValueList::GroupNames(atoms) => Some(atoms),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        // send all requests before waiting for any of the replies
+        let cookies = atoms
+            .into_iter()
+            .filter(|atom| !atom.is_none())
+            .map(|atom| conn.send_request(&x::GetAtomName { atom }))
+            .collect::<Vec<_>>();
+
+        let mut group_names = vec![];
+        for cookie in cookies {
+            let reply = conn.wait_for_reply(cookie)?;
+            group_names.push(reply.name().to_utf8().into_owned());
+        }
+
+        Ok(group_names)
+    }
+
+    /// Lock the keyboard to the given group.
+    pub fn lock_group(&self, group: usize) -> Result<(), Box<dyn Error>> {
+        let group_lock = match group {
+            0 => xkb::Group::N1,
+            1 => xkb::Group::N2,
+            2 => xkb::Group::N3,
+            3 => xkb::Group::N4,
+            _ => bail!("invalid group: {}, XKB supports at most 4 groups", group),
+        };
+
+        self.conn
+            .get_ref()
+            .send_and_check_request(&xkb::LatchLockState {
+                device_spec: DEVICE,
+                affect_mod_locks: x::ModMask::empty(),
+                mod_locks: x::ModMask::empty(),
+                lock_group: true,
+                group_lock,
+                affect_mod_latches: x::ModMask::empty(),
+                latch_group: false,
+                group_latch: 0,
+            })?;
+
+        Ok(())
+    }
+
+    /// Wait until the keyboard's state changes.
+    pub async fn wait_for_change(&self) -> Result<(), Box<dyn Error>> {
+        loop {
+            // events may have already been read (while waiting for replies), so check for those
+            // before waiting for the connection to be readable
+            let mut changed = false;
+            while let Some(event) = self.conn.get_ref().poll_for_event()? {
+                if let xcb::Event::Xkb(_) = event {
+                    changed = true;
+                }
+            }
+
+            if changed {
+                return Ok(());
+            }
+
+            self.conn.readable().await?.clear_ready();
+        }
+    }
+}
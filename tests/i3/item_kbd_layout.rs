@@ -0,0 +1,38 @@
+#![cfg(feature = "xkb")]
+
+use serde_json::{json, Value};
+
+use crate::i3::X11Test;
+
+x_test!(
+    item_kbd_layout,
+    json!({ "items": [{ "type": "kbd_layout" }] }),
+    |x_test: &X11Test| {
+        let short_text = |text: &'static str| move |bar: &Value| bar[0]["short_text"] == text;
+
+        // the default keymap of the X server
+        assert_json_contains!(
+            x_test.istat_wait_for_bar(short_text("English (US)")),
+            json!([{ "short_text": "English (US)" }])
+        );
+
+        // changing the keymap updates the item
+        x_test.cmd("setxkbmap -layout de,us");
+        assert_json_contains!(
+            x_test.istat_wait_for_bar(short_text("German")),
+            json!([{ "short_text": "German" }])
+        );
+
+        // clicking cycles through the layouts
+        x_test.istat_ipc("click kbd_layout left");
+        assert_json_contains!(
+            x_test.istat_wait_for_bar(short_text("English (US)")),
+            json!([{ "short_text": "English (US)" }])
+        );
+        x_test.istat_ipc("click kbd_layout left");
+        assert_json_contains!(
+            x_test.istat_wait_for_bar(short_text("German")),
+            json!([{ "short_text": "German" }])
+        );
+    }
+);
@@ -0,0 +1,29 @@
+use serde_json::{json, Value};
+
+use crate::i3::X11Test;
+
+x_test!(
+    item_mode,
+    json!({ "items": [{ "type": "mode" }] }),
+    |x_test: &X11Test| {
+        let full_text = |text: &'static str| move |bar: &Value| bar[0]["full_text"] == text;
+
+        // hidden in the default mode
+        assert_json_contains!(
+            x_test.istat_wait_for_bar(full_text("")),
+            json!([{ "full_text": "" }])
+        );
+
+        x_test.cmd("i3-msg mode resize");
+        assert_json_contains!(
+            x_test.istat_wait_for_bar(full_text("resize")),
+            json!([{ "full_text": "resize", "background": "#B48EAD" }])
+        );
+
+        x_test.cmd("i3-msg mode default");
+        assert_json_contains!(
+            x_test.istat_wait_for_bar(full_text("")),
+            json!([{ "full_text": "" }])
+        );
+    }
+);
@@ -1,8 +1,8 @@
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
-use std::{env, fs};
+use std::time::{Duration, Instant};
+use std::{env, fs, thread};
 
 use serde_json::Value;
 
@@ -35,6 +35,10 @@ font pango:IosevkaTerm Nerd Font 12
 
 bindsym Escape exit
 
+mode "resize" {{
+        bindsym Escape mode "default"
+}}
+
 ipc-socket {socket}
 
 bar {{
@@ -232,6 +236,20 @@ impl<'a> X11Test<'a> {
         self.istat_ipc("get-bar")
     }
 
+    /// Get the bar once it matches the predicate, for changes which happen asynchronously (e.g.,
+    /// items updating after a change in i3). Returns the last bar seen if it times out.
+    pub fn istat_wait_for_bar(&self, predicate: impl Fn(&Value) -> bool) -> Value {
+        let start = Instant::now();
+        loop {
+            let bar = self.istat_get_bar();
+            if predicate(&bar) || start.elapsed() > MAX_WAIT_TIME {
+                return bar;
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
     pub fn istat_ipc(&self, ipc_cmd: impl AsRef<str>) -> Value {
         let ipc = get_exe("istat-ipc");
         serde_json::from_slice(&self.cmd(format!(
@@ -0,0 +1,110 @@
+use std::process::{Command, Output};
+
+use serde_json::{json, Value};
+
+use crate::util::{get_current_exe, Test};
+
+fn check_config(name: &str, config: Value) -> Output {
+    let test = Test::new(name, config);
+    Command::new(get_current_exe())
+        .envs(&test.env)
+        .arg("--config")
+        .arg(&test.istat_config_file)
+        .arg("--check-config")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn check_config_valid() {
+    let output = check_config(
+        "check_config_valid",
+        json!({
+            "items": [
+                { "type": "raw", "full_text": "raw" },
+                { "type": "script", "command": "echo hello" },
+                {
+                    "type": "time",
+                    "interval": "1s",
+                    "format_long": "%Y-%m-%d",
+                    "format_short": "%H:%M"
+                }
+            ]
+        }),
+    );
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn check_config_invalid() {
+    let output = check_config(
+        "check_config_invalid",
+        json!({
+            "items": [
+                { "type": "raw", "full_text": "raw", "signal": 9001 },
+                { "type": "script", "name": "missing", "command": "not-a-real-program --arg" },
+                {
+                    "type": "time",
+                    "interval": "1s",
+                    "format_long": "%Y-%m-%d %Q",
+                    "format_short": "%H:%M"
+                }
+            ]
+        }),
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines = stderr.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "unexpected output: {}", stderr);
+    assert!(lines[0].starts_with("item[0] (raw): invalid signal: 9001"));
+    assert_eq!(
+        lines[1],
+        "item[1] (missing): command not found: not-a-real-program"
+    );
+    assert_eq!(lines[2], "item[2] (time): invalid time format: %Y-%m-%d %Q");
+}
+
+#[test]
+fn check_config_home_dir() {
+    let mut test = Test::new(
+        "check_config_home_dir",
+        json!({
+            "items": [
+                { "type": "script", "name": "home", "command": "~/bin/home-program --arg" },
+                { "type": "script", "name": "missing", "command": "~/bin/missing-program" },
+            ]
+        }),
+    );
+    // the test's `bin` directory is `~/bin`
+    test.env
+        .insert("HOME".into(), test.dir.to_str().unwrap().into());
+    test.add_bin("home-program", "#!/bin/sh");
+
+    let output = Command::new(get_current_exe())
+        .envs(&test.env)
+        .arg("--config")
+        .arg(&test.istat_config_file)
+        .arg("--check-config")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr).trim(),
+        "item[1] (missing): command not found: ~/bin/missing-program"
+    );
+}
+
+#[test]
+fn check_config_parse_error() {
+    let output = check_config(
+        "check_config_parse_error",
+        json!({ "items": [{ "type": "not-a-real-item" }] }),
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!output.stderr.is_empty());
+}
@@ -3,6 +3,7 @@ use istat::ipc::protocol::{IpcBarEvent, IpcMessage};
 use serde_json::{json, Value};
 
 use crate::spawn::SpawnedProgram;
+use crate::util::Test;
 
 spawn_test!(
     shutdown,
@@ -76,6 +77,103 @@ spawn_test!(
     }
 );
 
+spawn_test!(
+    reload,
+    json!({
+        "items": [
+            { "type": "raw", "full_text": "0" },
+            { "type": "raw", "full_text": "1" },
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "0", "name": "raw", "full_text": "0" },
+                { "instance": "1", "name": "raw", "full_text": "1" },
+            ])
+        );
+
+        istat.write_config(json!({
+            "items": [
+                { "type": "raw", "full_text": "1" },
+                { "type": "raw", "full_text": "2", "name": "new" },
+            ]
+        }));
+        assert_eq!(
+            istat.send_ipc(IpcMessage::Reload),
+            json!({ "result": { "detail": null, "type": "success" } })
+        );
+
+        // unchanged items are kept, and moved to their new index
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "0", "name": "raw", "full_text": "1" },
+                { "full_text": "" },
+            ])
+        );
+
+        // new items are started
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "0", "name": "raw", "full_text": "1" },
+                { "instance": "1", "name": "new", "full_text": "2" },
+            ])
+        );
+
+        assert_eq!(
+            istat.send_ipc(IpcMessage::Info),
+            json!({ "value": { "0": "raw", "1": "new" } })
+        );
+    }
+);
+
+spawn_test!(
+    reload_invalid,
+    json!({ "items": [{ "type": "raw", "full_text": "0" }] }),
+    |mut istat: SpawnedProgram| {
+        istat.write_config(json!({ "items": [{ "type": "not_an_item" }] }));
+        let reply = istat.send_ipc(IpcMessage::Reload);
+        assert_eq!(reply.pointer("/result/type").unwrap(), "failure");
+
+        // the bar is left as it was
+        assert_eq!(
+            istat.send_ipc(IpcMessage::Info),
+            json!({ "value": { "0": "raw" } })
+        );
+    }
+);
+
+#[test]
+fn reload_restarts_failed_items() {
+    let mut test = Test::new(
+        "reload_restarts_failed_items",
+        json!({ "items": [{ "type": "krb" }] }),
+    );
+    // `klist` can't be found at first, so the item fails
+    test.env
+        .insert("PATH".into(), test.bin_dir.to_str().unwrap().into());
+    let mut istat = SpawnedProgram::spawn(&test);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    // reloading the same config starts the failed item again
+    test.add_bin("klist", "#!/bin/sh\nexit 0");
+    assert_eq!(
+        istat.send_ipc(IpcMessage::Reload),
+        json!({ "result": { "detail": null, "type": "success" } })
+    );
+    assert_eq!(
+        istat.next_line_json().unwrap(),
+        json!([{ "full_text": "" }])
+    );
+    assert_eq!(
+        istat.next_line_json().unwrap(),
+        json!([{ "instance": "0", "name": "krb", "full_text": "󱕵", "markup": "pango", "color": "#D8DEE9" }])
+    );
+}
+
 spawn_test!(
     signal_item_index,
     json!({
@@ -305,3 +403,151 @@ spawn_test!(
         );
     }
 );
+
+spawn_test!(
+    subscribe,
+    json!({
+        "items": [
+            { "type": "script", "command": "echo -n button: ${I3_BUTTON:-none}", "output": "simple" },
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "button: none" }])
+        );
+
+        let mut events = istat.subscribe();
+
+        istat.click("0", I3Button::Left, &[]);
+
+        // the click event is sent first
+        let event = istat.next_event(&mut events);
+        assert_eq!(event["type"], "click");
+        assert_eq!(event.pointer("/detail/instance").unwrap(), "0");
+        assert_eq!(event.pointer("/detail/button").unwrap(), 1);
+
+        // followed by the bar update
+        assert_eq!(
+            istat.next_event(&mut events),
+            json!({
+                "type": "bar",
+                "detail": [{ "instance": "0", "name": "script", "full_text": "button: 1" }]
+            })
+        );
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "button: 1" }])
+        );
+
+        // theme changes are sent too
+        let mut theme = istat.send_ipc(IpcMessage::GetTheme)["value"].take();
+        theme["powerline_enable"] = json!(true);
+        assert_eq!(
+            istat.send_ipc(IpcMessage::SetTheme(theme.clone())),
+            json!({ "result": { "detail": null, "type": "success" } })
+        );
+        assert_eq!(
+            istat.next_event(&mut events),
+            json!({ "type": "theme", "detail": theme })
+        );
+    }
+);
+
+spawn_test!(
+    add_replace_remove_item,
+    json!({ "items": [{ "type": "raw", "full_text": "0" }] }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "raw", "full_text": "0" }])
+        );
+
+        // add
+        assert_eq!(
+            istat.send_ipc(IpcMessage::AddItem {
+                index: None,
+                item: json!({ "type": "raw", "name": "deploy", "full_text": "deploying" })
+            }),
+            json!({ "result": { "detail": "1", "type": "success" } })
+        );
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "0", "name": "raw", "full_text": "0" },
+                { "full_text": "" },
+            ])
+        );
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "0", "name": "raw", "full_text": "0" },
+                { "instance": "1", "name": "deploy", "full_text": "deploying" },
+            ])
+        );
+
+        // replace
+        assert_eq!(
+            istat.send_ipc(IpcMessage::ReplaceItem {
+                target: "deploy".into(),
+                item: json!({ "type": "raw", "name": "deploy", "full_text": "deployed" })
+            }),
+            json!({ "result": { "detail": null, "type": "success" } })
+        );
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "0", "name": "raw", "full_text": "0" },
+                { "full_text": "" },
+            ])
+        );
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "0", "name": "raw", "full_text": "0" },
+                { "instance": "1", "name": "deploy", "full_text": "deployed" },
+            ])
+        );
+
+        // remove
+        assert_eq!(
+            istat.send_ipc(IpcMessage::RemoveItem {
+                target: "deploy".into()
+            }),
+            json!({ "result": { "detail": null, "type": "success" } })
+        );
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "raw", "full_text": "0" }])
+        );
+        assert_eq!(
+            istat.send_ipc(IpcMessage::Info),
+            json!({ "value": { "0": "raw" } })
+        );
+    }
+);
+
+spawn_test!(
+    add_item_invalid,
+    json!({ "items": [{ "type": "raw", "full_text": "0", "name": "taken" }] }),
+    |mut istat: SpawnedProgram| {
+        // duplicate names aren't allowed
+        let reply = istat.send_ipc(IpcMessage::AddItem {
+            index: Some(0),
+            item: json!({ "type": "raw", "name": "taken", "full_text": "1" }),
+        });
+        assert_eq!(reply.pointer("/result/type").unwrap(), "failure");
+
+        // neither are unknown items
+        let reply = istat.send_ipc(IpcMessage::RemoveItem {
+            target: "unknown".into(),
+        });
+        assert_eq!(reply.pointer("/result/type").unwrap(), "failure");
+
+        // the bar is left as it was
+        assert_eq!(
+            istat.send_ipc(IpcMessage::Info),
+            json!({ "value": { "0": "taken" } })
+        );
+    }
+);
@@ -0,0 +1,177 @@
+use serde_json::json;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{dbus_interface, ConnectionBuilder};
+
+use crate::spawn::SpawnedProgram;
+use crate::util::{start_session_bus, Test};
+
+const DEVICE_PATH: &str = "/org/freedesktop/UPower/devices/battery_BAT0";
+
+/// A fake UPower daemon, which only implements what the `battery` item uses.
+struct MockUPower;
+
+#[dbus_interface(name = "org.freedesktop.UPower")]
+impl MockUPower {
+    fn enumerate_devices(&self) -> Vec<OwnedObjectPath> {
+        vec![OwnedObjectPath::try_from(DEVICE_PATH).unwrap()]
+    }
+
+    #[dbus_interface(property)]
+    fn on_battery(&self) -> bool {
+        true
+    }
+}
+
+/// A fake discharging 50 Wh battery, with its rate fixed at 10 W.
+struct MockDevice {
+    energy: f64,
+}
+
+#[dbus_interface(name = "org.freedesktop.UPower.Device")]
+impl MockDevice {
+    #[dbus_interface(property)]
+    fn native_path(&self) -> String {
+        "BAT0".into()
+    }
+
+    #[dbus_interface(property)]
+    fn model(&self) -> String {
+        "Mock Battery".into()
+    }
+
+    #[dbus_interface(property, name = "Type")]
+    fn kind(&self) -> u32 {
+        2
+    }
+
+    #[dbus_interface(property)]
+    fn power_supply(&self) -> bool {
+        true
+    }
+
+    #[dbus_interface(property)]
+    fn percentage(&self) -> f64 {
+        self.energy / 50.0 * 100.0
+    }
+
+    #[dbus_interface(property)]
+    fn state(&self) -> u32 {
+        2
+    }
+
+    #[dbus_interface(property)]
+    fn energy(&self) -> f64 {
+        self.energy
+    }
+
+    #[dbus_interface(property)]
+    fn energy_full(&self) -> f64 {
+        50.0
+    }
+
+    #[dbus_interface(property)]
+    fn energy_rate(&self) -> f64 {
+        10.0
+    }
+
+    #[dbus_interface(property)]
+    fn time_to_empty(&self) -> i64 {
+        (self.energy / 10.0 * 3600.0) as i64
+    }
+
+    #[dbus_interface(property)]
+    fn time_to_full(&self) -> i64 {
+        0
+    }
+}
+
+/// Run the mock UPower on the bus in the background, it lives until the test exits. Values sent
+/// to the returned channel change the battery's energy.
+fn start_mock_upower(address: String) -> UnboundedSender<f64> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (energy_tx, mut energy_rx) = unbounded_channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let connection = ConnectionBuilder::address(address.as_str())
+                .unwrap()
+                .name("org.freedesktop.UPower")
+                .unwrap()
+                .serve_at("/org/freedesktop/UPower", MockUPower)
+                .unwrap()
+                .serve_at(DEVICE_PATH, MockDevice { energy: 40.0 })
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            tx.send(()).unwrap();
+
+            let device = connection
+                .object_server()
+                .interface::<_, MockDevice>(DEVICE_PATH)
+                .await
+                .unwrap();
+            while let Some(energy) = energy_rx.recv().await {
+                device.get_mut().await.energy = energy;
+
+                let iface = device.get().await;
+                let ctxt = device.signal_context();
+                iface.energy_changed(ctxt).await.unwrap();
+                iface.percentage_changed(ctxt).await.unwrap();
+                iface.time_to_empty_changed(ctxt).await.unwrap();
+            }
+
+            std::future::pending::<()>().await;
+        });
+    });
+
+    rx.recv().unwrap();
+    energy_tx
+}
+
+#[test]
+fn battery_upower() {
+    let (_daemon, address) = start_session_bus();
+    let energy = start_mock_upower(address.clone());
+
+    let mut test = Test::new(
+        "battery_upower",
+        json!({ "items": [{ "type": "battery", "interval": "1s", "source": "upower" }] }),
+    );
+    // upower is on the system bus, and notifications are sent on the session bus
+    test.env
+        .insert("DBUS_SYSTEM_BUS_ADDRESS".into(), address.clone());
+    test.env.insert("DBUS_SESSION_BUS_ADDRESS".into(), address);
+    let mut istat = SpawnedProgram::spawn(&test);
+
+    assert_eq!(
+        istat.next_line_json().unwrap(),
+        json!([{
+            "instance": "0",
+            "name": "battery",
+            "full_text": "  80% 4:00",
+            "short_text": "80%",
+            "color": "#A3BE8C",
+            "markup": "pango"
+        }])
+    );
+
+    // the item refreshes when upower signals a change, rather than waiting for the interval
+    energy.send(20.0).unwrap();
+    assert_eq!(
+        istat.next_line_json().unwrap(),
+        json!([{
+            "instance": "0",
+            "name": "battery",
+            "full_text": "  40% 2:00",
+            "short_text": "40%",
+            "color": "#EBCB8B",
+            "markup": "pango"
+        }])
+    );
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use istat::i3::I3Button;
+use serde_json::json;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::{dbus_interface, ConnectionBuilder, SignalContext};
+
+use crate::spawn::SpawnedProgram;
+use crate::util::{start_session_bus, Test};
+
+/// A fake media player, which only implements what the `mpris` item uses.
+struct MockPlayer {
+    playing: bool,
+}
+
+#[dbus_interface(name = "org.mpris.MediaPlayer2.Player")]
+impl MockPlayer {
+    async fn play_pause(
+        &mut self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.playing = !self.playing;
+        self.playback_status_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    #[dbus_interface(property)]
+    fn playback_status(&self) -> String {
+        match self.playing {
+            true => "Playing".into(),
+            false => "Paused".into(),
+        }
+    }
+
+    #[dbus_interface(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        HashMap::from([
+            ("xesam:title".into(), Value::from("Title").into()),
+            ("xesam:artist".into(), Value::from(vec!["Artist"]).into()),
+        ])
+    }
+}
+
+/// Run the mock player on the bus in the background, it lives until the test exits.
+fn start_mock_player(address: String) {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let _connection = ConnectionBuilder::address(address.as_str())
+                .unwrap()
+                .name("org.mpris.MediaPlayer2.mock")
+                .unwrap()
+                .serve_at("/org/mpris/MediaPlayer2", MockPlayer { playing: true })
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            tx.send(()).unwrap();
+            std::future::pending::<()>().await;
+        });
+    });
+
+    rx.recv().unwrap();
+}
+
+#[test]
+fn mpris() {
+    let (_daemon, address) = start_session_bus();
+    start_mock_player(address.clone());
+
+    let mut test = Test::new("mpris", json!({ "items": [{ "type": "mpris" }] }));
+    test.env.insert("DBUS_SESSION_BUS_ADDRESS".into(), address);
+    let mut istat = SpawnedProgram::spawn(&test);
+
+    assert_eq!(
+        istat.next_line_json().unwrap(),
+        json!([{
+            "instance": "0",
+            "name": "mpris",
+            "full_text": "󰐊  Artist - Title",
+            "short_text": "Title",
+            "markup": "pango"
+        }])
+    );
+
+    // left click toggles play/pause
+    istat.click("0", I3Button::Left, &[]);
+    assert_eq!(
+        istat.next_line_json().unwrap(),
+        json!([{
+            "instance": "0",
+            "name": "mpris",
+            "full_text": "󰏤  Artist - Title",
+            "short_text": "Title",
+            "color": "#4C566A",
+            "markup": "pango"
+        }])
+    );
+}
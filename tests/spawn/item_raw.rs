@@ -1,6 +1,7 @@
 use serde_json::json;
 
 use crate::spawn::SpawnedProgram;
+use crate::util::Test;
 
 spawn_test!(
     raw,
@@ -22,3 +23,64 @@ spawn_test!(
         );
     }
 );
+
+spawn_test!(
+    raw_conditions,
+    json!({
+        "items": [
+            { "type": "raw", "full_text": "0", "hide_when": { "command": "true" } },
+            { "type": "raw", "full_text": "1", "show_when": { "file": "/" } },
+            { "type": "raw", "full_text": "2", "show_when": { "file": "/does/not/exist" } },
+            { "type": "raw", "full_text": "3", "hide_when": { "expr": "full_text == 3" } },
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "1", "full_text": "1", "name": "raw" },
+            ])
+        );
+    }
+);
+
+#[test]
+fn raw_conditions_interval() {
+    let mut test = Test::new(
+        "raw_conditions_interval",
+        json!({
+            "items": [
+                { "type": "raw", "full_text": "0" },
+                {
+                    "type": "raw",
+                    "full_text": "1",
+                    "show_when": { "command": "test -e \"$CONDITION_FILE\"" }
+                },
+            ]
+        }),
+    );
+    let file = test.dir.join("condition");
+    test.env
+        .insert("CONDITION_FILE".into(), file.to_str().unwrap().into());
+    let mut istat = SpawnedProgram::spawn(&test);
+
+    assert_eq!(
+        istat.next_line_json().unwrap(),
+        json!([
+            { "instance": "0", "full_text": "0", "name": "raw" },
+        ])
+    );
+
+    // the item has finished, but its condition is still checked (every 5 seconds)
+    std::fs::write(&file, "").unwrap();
+    let line = (0..5)
+        .find_map(|_| istat.next_line_json().ok())
+        .expect("item wasn't shown");
+    assert_eq!(
+        line,
+        json!([
+            { "instance": "0", "full_text": "0", "name": "raw" },
+            { "instance": "1", "full_text": "1", "name": "raw" },
+        ])
+    );
+}
@@ -1,7 +1,9 @@
-use istat::i3::I3Button;
+use istat::i3::{I3Button, I3Modifier};
+use istat::ipc::protocol::IpcMessage;
 use serde_json::json;
 
 use crate::spawn::SpawnedProgram;
+use crate::util::Test;
 
 spawn_test!(
     script_simple,
@@ -78,3 +80,236 @@ spawn_test!(
         );
     }
 );
+
+spawn_test!(
+    script_on_click,
+    json!({
+        "items":[
+            {
+                "type": "script",
+                "command": "echo -n button=${I3_BUTTON:-none}",
+                "output": "simple",
+                "on_click": {
+                    "right": { "command": "true" },
+                    "shift+middle": { "command": "true" },
+                }
+            }
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "button=none" }])
+        );
+
+        // these clicks are handled by `on_click`, so they never reach the item
+        istat.click("0", I3Button::Right, &[]);
+        istat.click("0", I3Button::Middle, &[I3Modifier::Shift]);
+
+        // other clicks still reach the item
+        istat.click("0", I3Button::Middle, &[]);
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "button=2" }])
+        );
+    }
+);
+
+spawn_test!(
+    script_format,
+    json!({
+        "items":[
+            {
+                "type": "script",
+                "command": "echo -n hello",
+                "output": "simple",
+                "format": "<b>{full_text}</b> {unknown}",
+                "format_short": "{short_text}!",
+            }
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                {
+                    "instance": "0",
+                    "name": "script",
+                    "full_text": "<b>hello</b> {unknown}",
+                    "short_text": "hello!",
+                    "markup": "pango",
+                }
+            ])
+        );
+    }
+);
+
+spawn_test!(
+    script_persistent,
+    json!({
+        "items":[
+            {
+                "type": "script",
+                "command": r#"echo started; while read -r click; do echo "$click" | grep -o '"button":[0-9]*'; done"#,
+                "persistent": true,
+            }
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "started" }])
+        );
+
+        istat.click("0", I3Button::Right, &[]);
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "\"button\":3" }])
+        );
+    }
+);
+
+spawn_test!(
+    script_persistent_json,
+    json!({
+        "items":[
+            {
+                "type": "script",
+                "command": r#"echo '{"full_text":"one"}'; sleep 0.1; echo '{"full_text":"two"}'; sleep 10"#,
+                "output": "json",
+                "persistent": true,
+            }
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "one" }])
+        );
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "two" }])
+        );
+    }
+);
+
+spawn_test!(
+    script_persistent_restarts_on_error,
+    json!({
+        "items":[
+            {
+                "type": "script",
+                // invalid UTF-8 fails reading the script's output
+                "command": r#"echo x >> "$COUNTER"; echo "run $(wc -l < "$COUNTER")"; printf '\377\n'; sleep 10"#,
+                "persistent": true,
+            }
+        ]
+    }),
+    |test: &mut Test| {
+        let counter = test.dir.join("counter").display().to_string();
+        test.env.insert("COUNTER".into(), counter);
+    },
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "run 1" }])
+        );
+        // restarted after the backoff, rather than ending the item
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([{ "instance": "0", "name": "script", "full_text": "run 2" }])
+        );
+    }
+);
+
+spawn_test!(
+    script_exit_codes,
+    json!({
+        "items":[
+            { "type": "script", "command": "echo -n ok" },
+            { "type": "script", "command": "echo -n urgent; exit 33" },
+            { "type": "script", "command": "echo -n hidden; exit 34" },
+            { "type": "script", "command": "echo -n error; exit 1" },
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        istat.wait_for_all_init();
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                { "instance": "0", "name": "script", "full_text": "ok" },
+                { "instance": "1", "name": "script", "full_text": "urgent", "urgent": true },
+                {
+                    "instance": "3",
+                    "name": "script",
+                    "full_text": "error",
+                    "color": "#2E3440",
+                    "background": "#BF616A"
+                },
+            ])
+        );
+    }
+);
+
+spawn_test!(
+    script_timeout_and_logs,
+    json!({
+        "items":[
+            {
+                "type": "script",
+                "name": "slow",
+                "command": "echo starting >&2; sleep 10; echo -n done",
+                "timeout": "100ms"
+            },
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                {
+                    "instance": "0",
+                    "name": "slow",
+                    "full_text": "TIMEOUT",
+                    "color": "#2E3440",
+                    "background": "#BF616A"
+                },
+            ])
+        );
+
+        assert_eq!(
+            istat.send_ipc(IpcMessage::GetItemLogs {
+                target: "slow".into()
+            }),
+            // anything the script wrote before timing out is kept
+            json!({ "value": ["starting", "timed out after 100ms"] })
+        );
+    }
+);
+
+spawn_test!(
+    script_i3blocks,
+    json!({
+        "items":[
+            {
+                "type": "script",
+                "command": "printf 'full text\\nshort\\n#ff0000\\n'",
+                "output": "i3blocks",
+            }
+        ]
+    }),
+    |mut istat: SpawnedProgram| {
+        assert_eq!(
+            istat.next_line_json().unwrap(),
+            json!([
+                {
+                    "instance": "0",
+                    "name": "script",
+                    "full_text": "full text",
+                    "short_text": "short",
+                    "color": "#FF0000",
+                }
+            ])
+        );
+    }
+);
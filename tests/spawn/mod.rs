@@ -23,6 +23,7 @@ use crate::util::{
 pub struct SpawnedProgram {
     #[allow(unused)]
     child: LogOnDropChild,
+    config: PathBuf,
     socket: PathBuf,
     stdin: ChildStdin,
     stdout: BufReader<TimeoutReader<ChildStdout>>,
@@ -64,6 +65,7 @@ impl SpawnedProgram {
 
         let mut test = SpawnedProgram {
             child,
+            config: test.istat_config_file.clone(),
             socket: test.istat_socket_file.clone(),
             stdin,
             stdout,
@@ -71,8 +73,12 @@ impl SpawnedProgram {
 
         // assert header
         assert_eq!(
-            test.next_line().unwrap().as_deref(),
-            Some(r#"{"version":1,"click_events":true}"#)
+            test.next_line().unwrap(),
+            Some(format!(
+                r#"{{"version":1,"stop_signal":{},"cont_signal":{},"click_events":true}}"#,
+                libc::SIGTSTP,
+                libc::SIGCONT
+            ))
         );
         assert_eq!(test.next_line().unwrap().as_deref(), Some(r#"["#));
 
@@ -121,6 +127,30 @@ impl SpawnedProgram {
         serde_json::from_slice::<Value>(&buf[IPC_HEADER_LEN..]).unwrap()
     }
 
+    /// Subscribe to events via IPC, use `next_event` to read them
+    pub fn subscribe(&mut self) -> UnixStream {
+        let mut stream = UnixStream::connect(&self.socket).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        stream
+            .write_all(&encode_ipc_msg(IpcMessage::Subscribe).unwrap())
+            .unwrap();
+
+        // wait until we're subscribed
+        assert_eq!(
+            read_ipc_msg(&mut stream),
+            serde_json::json!({ "result": { "type": "success", "detail": null } })
+        );
+
+        stream
+    }
+
+    /// Read the next event from a stream returned by `subscribe`
+    pub fn next_event(&mut self, stream: &mut UnixStream) -> Value {
+        read_ipc_msg(stream)
+    }
+
     /// Send a shutdown request via IPC
     pub fn send_shutdown(&mut self) {
         let reply = self.send_ipc(IpcMessage::Shutdown);
@@ -138,6 +168,11 @@ impl SpawnedProgram {
         }
     }
 
+    /// Overwrite the program's config file (it won't be read until the program is told to reload it)
+    pub fn write_config(&mut self, config: Value) {
+        std::fs::write(&self.config, config.to_string()).unwrap();
+    }
+
     /// Perform an assertion on the next line as JSON
     pub fn next_line_json(&mut self) -> Result<Value, Box<dyn std::error::Error>> {
         let next_line = self.next_line()?;
@@ -155,6 +190,15 @@ impl SpawnedProgram {
     }
 }
 
+/// Read a single IPC message from a stream which is kept open
+fn read_ipc_msg(stream: &mut UnixStream) -> Value {
+    let mut header = [0; IPC_HEADER_LEN];
+    stream.read_exact(&mut header).unwrap();
+    let mut body = vec![0; u64::from_le_bytes(header) as usize];
+    stream.read_exact(&mut body).unwrap();
+    serde_json::from_slice::<Value>(&body).unwrap()
+}
+
 macro_rules! spawn_test {
     ($name:ident, $config:expr, $test_fn:expr) => {
         spawn_test!($name, $config, |x| x, $test_fn);
@@ -0,0 +1,76 @@
+use std::process::{Command, Output};
+
+use serde_json::{json, Value};
+
+use crate::util::{get_current_exe, Test};
+
+fn once(name: &str, config: Value, args: &[&str]) -> Output {
+    let test = Test::new(name, config);
+    Command::new(get_current_exe())
+        .envs(&test.env)
+        .env_remove("I3SOCK")
+        .arg("--config")
+        .arg(&test.istat_config_file)
+        .arg("--once")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn once_text() {
+    let output = once(
+        "once_text",
+        json!({
+            "items": [
+                { "type": "raw", "full_text": "foo" },
+                { "type": "raw", "full_text": "bar" }
+            ]
+        }),
+        &[],
+    );
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "foo | bar\n");
+}
+
+#[test]
+fn once_json() {
+    let output = once(
+        "once_json",
+        json!({
+            "items": [
+                { "type": "raw", "full_text": "foo" },
+                { "type": "script", "command": "echo bar" }
+            ]
+        }),
+        &["--format", "json"],
+    );
+
+    assert!(output.status.success());
+    let bar = serde_json::from_slice::<Value>(&output.stdout).unwrap();
+    assert_eq!(
+        bar,
+        json!([
+            { "name": "raw", "instance": "0", "full_text": "foo" },
+            { "name": "script", "instance": "1", "full_text": "bar" }
+        ])
+    );
+}
+
+#[test]
+fn once_timeout() {
+    let output = once(
+        "once_timeout",
+        json!({
+            "items": [
+                { "type": "raw", "full_text": "foo" },
+                { "type": "script", "command": "sleep 5; echo bar" }
+            ]
+        }),
+        &["--timeout", "100ms"],
+    );
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "foo\n");
+}
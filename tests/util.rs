@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
-use std::process::Child;
+use std::process::{Child, Command, Stdio};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 use std::{env, fs, thread};
@@ -152,6 +152,25 @@ impl Drop for LogOnDropChild {
     }
 }
 
+/// Start a private session bus, and return its address.
+pub fn start_session_bus() -> (LogOnDropChild, String) {
+    let mut daemon = LogOnDropChild::log_stderr(
+        Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap(),
+    );
+
+    let mut address = String::new();
+    BufReader::new(daemon.stdout.take().unwrap())
+        .read_line(&mut address)
+        .unwrap();
+
+    (daemon, address.trim().into())
+}
+
 // test ------------------------------------------------------------------------
 
 static UNIQUE_ID: AtomicUsize = AtomicUsize::new(0);